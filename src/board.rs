@@ -0,0 +1,50 @@
+use crate::document::Document;
+use crate::history::History;
+use iced::Color;
+
+/// What the ink is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Board {
+    /// The transparent overlay on top of the screen.
+    Overlay,
+    Whiteboard,
+    Blackboard,
+}
+
+impl Board {
+    pub const ALL: [Board; 3] = [Board::Overlay, Board::Whiteboard, Board::Blackboard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Board::Overlay => "overlay",
+            Board::Whiteboard => "whiteboard",
+            Board::Blackboard => "blackboard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|board| board.name() == name)
+    }
+
+    pub fn background(self) -> Color {
+        match self {
+            Board::Overlay => Color::TRANSPARENT,
+            Board::Whiteboard => Color::WHITE,
+            Board::Blackboard => Color::from_rgb(0.08, 0.08, 0.08),
+        }
+    }
+
+    pub fn text_color(self) -> Color {
+        match self {
+            Board::Blackboard => Color::WHITE,
+            _ => Color::BLACK,
+        }
+    }
+}
+
+/// The ink of a board that is not being shown.
+#[derive(Debug, Default)]
+pub struct Sheet {
+    pub document: Document,
+    pub history: History,
+}
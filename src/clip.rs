@@ -0,0 +1,89 @@
+use crate::document::{Document, Stroke};
+use crate::session::{self, StrokeData};
+use crate::smoothing::Smoothing;
+use crate::svg;
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+/// Copied ink, kept in the metadata of the copied image.
+#[derive(Serialize, Deserialize)]
+struct Clip {
+    /// Names the format, so metadata written by others is not pasted.
+    #[serde(rename = "vivopaint-clip")]
+    version: u64,
+    strokes: Vec<StrokeData>,
+}
+
+const VERSION: u64 = 1;
+
+/// Renders strokes as an SVG image that other applications can paste,
+/// carrying the strokes themselves for VivoPaint. Returns `None` if there is
+/// nothing to copy.
+pub fn encode(strokes: &[Stroke], smoothing: Smoothing) -> Option<String> {
+    if strokes.is_empty() {
+        return None;
+    }
+
+    let clip = Clip {
+        version: VERSION,
+        strokes: strokes.iter().map(session::stroke_data).collect(),
+    };
+    let json = serde_json::to_string(&clip).ok()?;
+
+    let mut document = Document::new();
+    document.restore_strokes(strokes.to_vec());
+
+    Some(svg::export(&document, smoothing, Color::TRANSPARENT, Some(&json)))
+}
+
+/// Reads strokes copied by [`encode`], as new finished strokes. Returns
+/// `None` if `text` is not copied ink.
+pub fn decode(text: &str) -> Option<Vec<Stroke>> {
+    let (_, rest) = text.split_once("<metadata>")?;
+    let (metadata, _) = rest.split_once("</metadata>")?;
+
+    let clip: Clip = serde_json::from_str(&svg::unescape(metadata)).ok()?;
+
+    if clip.version != VERSION {
+        return None;
+    }
+
+    clip.strokes.iter().map(session::stroke).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Tool;
+    use iced::Point;
+
+    #[test]
+    fn copied_ink_is_an_image_that_pastes_back() {
+        let red = Color::from_rgb(1.0, 0.0, 0.0);
+        let mut stroke = Stroke::new(Point::ORIGIN, red, 3.0, Tool::Pen);
+        stroke.push(Point::new(20.0, 10.0));
+        stroke.push(Point::new(40.0, 0.0));
+        stroke.raw_points = Some(vec![Point::ORIGIN, Point::new(10.0, 6.0), Point::new(40.0, 0.0)]);
+
+        let text = encode(&[stroke.clone()], Smoothing::default()).unwrap();
+
+        assert!(text.starts_with("<svg "));
+        assert!(text.contains("<path "));
+
+        let pasted = decode(&text).unwrap();
+
+        assert_eq!(pasted.len(), 1);
+        assert_ne!(pasted[0].id, stroke.id);
+        assert_eq!(pasted[0].tool, stroke.tool);
+        assert_eq!(pasted[0].color, stroke.color);
+        assert_eq!(pasted[0].points, stroke.points);
+        assert_eq!(pasted[0].raw_points, stroke.raw_points);
+    }
+
+    #[test]
+    fn other_text_is_not_pasted() {
+        assert!(decode("hello").is_none());
+        assert!(decode(r#"<svg><metadata>{"title": "other"}</metadata></svg>"#).is_none());
+        assert!(encode(&[], Smoothing::default()).is_none());
+    }
+}
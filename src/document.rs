@@ -0,0 +1,358 @@
+use crate::geometry::Affine;
+use crate::simplify::{self, Simplify};
+use iced::{Color, Point, Rectangle, Size};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Pen,
+    Highlighter,
+    Line,
+    Arrow,
+    Rectangle,
+    Ellipse,
+    Triangle,
+}
+
+impl Tool {
+    /// Shapes are dragged out from an anchor instead of drawn freehand.
+    pub fn is_shape(self) -> bool {
+        matches!(
+            self,
+            Tool::Line | Tool::Arrow | Tool::Rectangle | Tool::Ellipse | Tool::Triangle
+        )
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pen => "pen",
+            Tool::Highlighter => "highlighter",
+            Tool::Line => "line",
+            Tool::Arrow => "arrow",
+            Tool::Rectangle => "rectangle",
+            Tool::Ellipse => "ellipse",
+            Tool::Triangle => "triangle",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Tool::Pen,
+            Tool::Highlighter,
+            Tool::Line,
+            Tool::Arrow,
+            Tool::Rectangle,
+            Tool::Ellipse,
+            Tool::Triangle,
+        ]
+        .into_iter()
+        .find(|tool| tool.name() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    /// Identifies the stroke across undo, redo and erasing; fragments get new ids.
+    pub id: u64,
+    pub points: Vec<Point>,
+    /// The samples as recorded, if they were kept when the stroke was simplified.
+    pub raw_points: Option<Vec<Point>>,
+    pub color: Color,
+    pub width: f32,
+    pub tool: Tool,
+    pub finished_at: Option<Instant>,
+    /// Disappearing ink fades out this long after the stroke is finished.
+    pub fade_after: Option<Duration>,
+}
+
+impl Stroke {
+    pub fn new(start: Point, color: Color, width: f32, tool: Tool) -> Self {
+        Self {
+            id: next_id(),
+            points: vec![start],
+            raw_points: None,
+            color,
+            width,
+            tool,
+            finished_at: None,
+            fade_after: None,
+        }
+    }
+
+    /// A copy of this stroke under a new id.
+    pub fn duplicate(&self) -> Self {
+        Self {
+            id: next_id(),
+            ..self.clone()
+        }
+    }
+
+    /// A new stroke with the same style as this one, covering part of it.
+    pub fn fragment(&self, points: Vec<Point>) -> Self {
+        // Only the piece that still ends at the tip keeps the arrow head.
+        let tool = match self.tool {
+            Tool::Arrow if points.last() != self.points.last() => Tool::Line,
+            tool => tool,
+        };
+
+        Self {
+            id: next_id(),
+            points,
+            tool,
+            raw_points: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn push(&mut self, position: Point) {
+        if self.points.last() != Some(&position) {
+            self.points.push(position);
+        }
+    }
+
+    /// Whether the stroke is a shape that was never dragged out.
+    pub fn is_degenerate(&self) -> bool {
+        self.tool.is_shape() && self.points.windows(2).all(|pair| pair[0] == pair[1])
+    }
+
+    pub fn finish(&mut self) {
+        self.finished_at = Some(Instant::now());
+    }
+
+    pub fn transform(&mut self, transform: &Affine) {
+        for point in self.points.iter_mut().chain(self.raw_points.iter_mut().flatten()) {
+            *point = transform.apply(*point);
+        }
+
+        self.width *= transform.scale_factor();
+    }
+
+    pub fn simplify(&mut self, options: Simplify) {
+        let simplified = simplify::simplify(&self.points, options.tolerance);
+
+        if simplified.len() == self.points.len() {
+            return;
+        }
+
+        let raw = std::mem::replace(&mut self.points, simplified);
+
+        if options.keep_raw {
+            self.raw_points = Some(raw);
+        }
+    }
+}
+
+/// A line of text placed on the canvas, drawn in a monospace font so its
+/// extent can be known without measuring it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub id: u64,
+    /// The top left corner of the text.
+    pub position: Point,
+    pub content: String,
+    pub color: Color,
+    pub size: f32,
+}
+
+impl Label {
+    /// Advance of a monospace glyph, relative to the font size.
+    pub const ADVANCE: f32 = 0.6;
+    pub const LINE_HEIGHT: f32 = 1.3;
+
+    pub fn new(position: Point, color: Color, size: f32) -> Self {
+        Self {
+            id: next_id(),
+            position,
+            content: String::new(),
+            color,
+            size,
+        }
+    }
+
+    pub fn bounds(&self) -> Rectangle {
+        let columns = self.content.chars().count().max(1) as f32;
+
+        Rectangle::new(
+            self.position,
+            Size::new(columns * self.size * Self::ADVANCE, self.size * Self::LINE_HEIGHT),
+        )
+    }
+}
+
+/// The ordered list of strokes on the canvas, plus the one being drawn.
+#[derive(Debug, Default)]
+pub struct Document {
+    strokes: Vec<Stroke>,
+    labels: Vec<Label>,
+    active: Option<Stroke>,
+    /// Disappearing ink, kept apart from `strokes` since it is not part of
+    /// the undo history.
+    fading: Vec<Stroke>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    /// The topmost label under `position`.
+    pub fn label_at(&self, position: Point) -> Option<usize> {
+        self.labels
+            .iter()
+            .rposition(|label| label.bounds().contains(position))
+    }
+
+    pub fn active(&self) -> Option<&Stroke> {
+        self.active.as_ref()
+    }
+
+    pub fn fading(&self) -> &[Stroke] {
+        &self.fading
+    }
+
+    pub fn is_drawing(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Starts a new active stroke, replacing any unfinished one.
+    pub fn begin_stroke(&mut self, stroke: Stroke) {
+        self.active = Some(stroke);
+    }
+
+    pub fn extend_stroke(&mut self, position: Point) {
+        if let Some(stroke) = self.active.as_mut() {
+            stroke.push(position);
+        }
+    }
+
+    /// Replaces the active stroke's outline, e.g. while dragging out a shape.
+    pub fn reshape_stroke(&mut self, tool: Tool, points: Vec<Point>) {
+        if let Some(stroke) = self.active.as_mut() {
+            stroke.tool = tool;
+            stroke.points = points;
+        }
+    }
+
+    /// Closes the active stroke and appends it to the document, returning its
+    /// index. Disappearing ink goes to the fading strokes instead.
+    pub fn end_stroke(&mut self, simplify: Simplify) -> Option<usize> {
+        let mut stroke = self.active.take()?;
+
+        if stroke.is_degenerate() {
+            return None;
+        }
+
+        stroke.simplify(simplify);
+        stroke.finish();
+
+        if stroke.fade_after.is_some() {
+            self.fading.push(stroke);
+            return None;
+        }
+
+        self.strokes.push(stroke);
+
+        Some(self.strokes.len() - 1)
+    }
+
+    /// Keeps the fading stroke at `index`, returning its new index.
+    pub fn pin_fading(&mut self, index: usize) -> Option<usize> {
+        if index >= self.fading.len() {
+            return None;
+        }
+
+        let mut stroke = self.fading.remove(index);
+        stroke.fade_after = None;
+        self.strokes.push(stroke);
+
+        Some(self.strokes.len() - 1)
+    }
+
+    /// Drops the most recent fading stroke, returning whether there was one.
+    pub fn drop_fading(&mut self) -> bool {
+        self.fading.pop().is_some()
+    }
+
+    /// Drops the fading strokes for which `is_gone` holds.
+    pub fn expire(&mut self, is_gone: impl Fn(&Stroke) -> bool) {
+        self.fading.retain(|stroke| !is_gone(stroke));
+    }
+
+    pub fn clear_fading(&mut self) {
+        self.fading.clear();
+    }
+
+    /// Drops the active stroke without committing it.
+    pub fn cancel_stroke(&mut self) -> bool {
+        self.active.take().is_some()
+    }
+
+    pub fn insert(&mut self, index: usize, stroke: Stroke) {
+        self.strokes.insert(index.min(self.strokes.len()), stroke);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Stroke> {
+        (index < self.strokes.len()).then(|| self.strokes.remove(index))
+    }
+
+    /// Replaces the stroke at `index`, returning the previous one.
+    pub fn replace(&mut self, index: usize, stroke: Stroke) -> Option<Stroke> {
+        self.strokes
+            .get_mut(index)
+            .map(|current| std::mem::replace(current, stroke))
+    }
+
+    /// Replaces the stroke at `index` with `strokes`.
+    pub fn splice(&mut self, index: usize, strokes: Vec<Stroke>) {
+        if index < self.strokes.len() {
+            self.strokes.splice(index..=index, strokes);
+        }
+    }
+
+    pub fn take_strokes(&mut self) -> Vec<Stroke> {
+        self.active = None;
+        std::mem::take(&mut self.strokes)
+    }
+
+    pub fn restore_strokes(&mut self, strokes: Vec<Stroke>) {
+        self.strokes = strokes;
+    }
+
+    pub fn insert_label(&mut self, index: usize, label: Label) {
+        self.labels.insert(index.min(self.labels.len()), label);
+    }
+
+    pub fn remove_label(&mut self, index: usize) -> Option<Label> {
+        (index < self.labels.len()).then(|| self.labels.remove(index))
+    }
+
+    /// Replaces the label at `index`, returning the previous one.
+    pub fn replace_label(&mut self, index: usize, label: Label) -> Option<Label> {
+        self.labels
+            .get_mut(index)
+            .map(|current| std::mem::replace(current, label))
+    }
+
+    pub fn take_labels(&mut self) -> Vec<Label> {
+        std::mem::take(&mut self.labels)
+    }
+
+    pub fn restore_labels(&mut self, labels: Vec<Label>) {
+        self.labels = labels;
+    }
+}
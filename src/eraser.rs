@@ -0,0 +1,124 @@
+use crate::document::{Document, Stroke};
+use crate::geometry::{distance_to_segment, segment_distance};
+use crate::simplify::simplify;
+use iced::Point;
+
+/// Tolerance used to drop the extra samples added when cutting a segment.
+const FRAGMENT_TOLERANCE: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eraser {
+    /// Removes every stroke the eraser touches.
+    Stroke,
+    /// Removes only the parts of strokes under the eraser, splitting them.
+    Precise,
+}
+
+impl Eraser {
+    /// Cycles through no eraser, the stroke eraser and the precise eraser.
+    pub fn cycle(current: Option<Eraser>) -> Option<Eraser> {
+        match current {
+            None => Some(Eraser::Stroke),
+            Some(Eraser::Stroke) => Some(Eraser::Precise),
+            Some(Eraser::Precise) => None,
+        }
+    }
+
+    /// Erases along the eraser's movement from `from` to `to`, returning
+    /// whether the document changed.
+    pub fn erase(self, document: &mut Document, from: Point, to: Point, radius: f32) -> bool {
+        let mut changed = false;
+
+        for index in (0..document.strokes().len()).rev() {
+            let stroke = &document.strokes()[index];
+
+            match self {
+                Eraser::Stroke => {
+                    if touches(&stroke.points, from, to, radius + stroke.width / 2.0) {
+                        document.remove(index);
+                        changed = true;
+                    }
+                }
+                Eraser::Precise => {
+                    if let Some(runs) = split(&stroke.points, from, to, radius) {
+                        let fragments = runs
+                            .into_iter()
+                            .map(|points| stroke.fragment(points))
+                            .collect::<Vec<Stroke>>();
+
+                        document.splice(index, fragments);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+fn touches(points: &[Point], from: Point, to: Point, reach: f32) -> bool {
+    match points {
+        [] => false,
+        [point] => distance_to_segment(*point, from, to) <= reach,
+        _ => points
+            .windows(2)
+            .any(|pair| segment_distance(pair[0], pair[1], from, to) <= reach),
+    }
+}
+
+/// Cuts the parts of a polyline within `radius` of the eraser segment,
+/// returning the remaining runs, or `None` if the eraser missed it.
+fn split(points: &[Point], from: Point, to: Point, radius: f32) -> Option<Vec<Vec<Point>>> {
+    if !touches(points, from, to, radius) {
+        return None;
+    }
+
+    let erased = |point: Point| distance_to_segment(point, from, to) <= radius;
+    let step = (radius / 2.0).max(0.5);
+
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+
+    let mut visit = |point: Point| {
+        if erased(point) {
+            if run.len() > 1 {
+                runs.push(std::mem::take(&mut run));
+            } else {
+                run.clear();
+            }
+        } else {
+            run.push(point);
+        }
+    };
+
+    for pair in points.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+
+        if segment_distance(start, end, from, to) > radius {
+            visit(start);
+            continue;
+        }
+
+        // Resample segments the eraser crosses so the cut lands near its edge.
+        let samples = (start.distance(end) / step).ceil().max(1.0) as usize;
+
+        for i in 0..samples {
+            visit(start + (end - start) * (i as f32 / samples as f32));
+        }
+    }
+
+    if let Some(last) = points.last() {
+        visit(*last);
+    }
+
+    if run.len() > 1 {
+        runs.push(run);
+    }
+
+    Some(
+        runs.into_iter()
+            .map(|run| simplify(&run, FRAGMENT_TOLERANCE))
+            .collect(),
+    )
+}
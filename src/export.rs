@@ -0,0 +1,35 @@
+use crate::document::{Document, Label};
+use crate::selection;
+use iced::{Point, Rectangle, Size};
+
+/// Empty space kept around exported ink.
+const MARGIN: f32 = 16.0;
+
+/// The area exported from `document`: its ink and labels with a margin.
+pub fn extent(document: &Document) -> Rectangle {
+    let ink = selection::bounds(document.strokes());
+    let text = document.labels().iter().map(Label::bounds);
+
+    let bounds = ink
+        .into_iter()
+        .chain(text)
+        .reduce(|a, b| {
+            let x = a.x.min(b.x);
+            let y = a.y.min(b.y);
+
+            Rectangle {
+                x,
+                y,
+                width: (a.x + a.width).max(b.x + b.width) - x,
+                height: (a.y + a.height).max(b.y + b.height) - y,
+            }
+        })
+        .unwrap_or(Rectangle::new(Point::ORIGIN, Size::ZERO));
+
+    Rectangle {
+        x: bounds.x - MARGIN,
+        y: bounds.y - MARGIN,
+        width: bounds.width + MARGIN * 2.0,
+        height: bounds.height + MARGIN * 2.0,
+    }
+}
@@ -0,0 +1,71 @@
+use crate::document::{Stroke, Tool};
+use std::time::{Duration, Instant};
+
+/// How long a stroke takes to disappear once its delay is over.
+pub const FADE_OUT: Duration = Duration::from_millis(600);
+
+/// How long disappearing ink stays fully visible, set separately for the
+/// pen, the highlighter and the shape tools.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delays {
+    pen: Duration,
+    highlighter: Duration,
+    shapes: Duration,
+}
+
+impl Delays {
+    pub const STEPS: [Duration; 6] = [
+        Duration::from_secs(1),
+        Duration::from_secs(2),
+        Duration::from_secs(3),
+        Duration::from_secs(5),
+        Duration::from_secs(6),
+        Duration::from_secs(10),
+    ];
+
+    pub fn get(&self, tool: Tool) -> Duration {
+        match tool {
+            Tool::Pen => self.pen,
+            Tool::Highlighter => self.highlighter,
+            _ => self.shapes,
+        }
+    }
+
+    /// Moves the delay used by `tool` to the next step, returning it.
+    pub fn next(&mut self, tool: Tool) -> Duration {
+        let delay = match tool {
+            Tool::Pen => &mut self.pen,
+            Tool::Highlighter => &mut self.highlighter,
+            _ => &mut self.shapes,
+        };
+
+        let next = Self::STEPS
+            .iter()
+            .position(|step| *step > *delay)
+            .unwrap_or(0);
+
+        *delay = Self::STEPS[next];
+        *delay
+    }
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Self {
+            pen: Duration::from_secs(3),
+            highlighter: Duration::from_secs(5),
+            shapes: Duration::from_secs(6),
+        }
+    }
+}
+
+/// The opacity of a stroke at `now`, from `1.0` down to `0.0` once it has faded.
+pub fn opacity(stroke: &Stroke, now: Instant) -> f32 {
+    let (Some(delay), Some(finished_at)) = (stroke.fade_after, stroke.finished_at) else {
+        return 1.0;
+    };
+
+    let fading_for = now.saturating_duration_since(finished_at).saturating_sub(delay);
+
+    (1.0 - fading_for.as_secs_f32() / FADE_OUT.as_secs_f32()).max(0.0)
+}
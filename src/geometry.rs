@@ -0,0 +1,112 @@
+use iced::{Point, Vector};
+
+pub fn distance_to_segment(point: Point, a: Point, b: Point) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.x * ab.x + ab.y * ab.y;
+
+    if length_squared == 0.0 {
+        return point.distance(a);
+    }
+
+    let ap = point - a;
+    let t = ((ap.x * ab.x + ap.y * ab.y) / length_squared).clamp(0.0, 1.0);
+
+    point.distance(a + ab * t)
+}
+
+/// Shortest distance between the segments `a`–`b` and `c`–`d`.
+pub fn segment_distance(a: Point, b: Point, c: Point, d: Point) -> f32 {
+    if segments_intersect(a, b, c, d) {
+        return 0.0;
+    }
+
+    distance_to_segment(a, c, d)
+        .min(distance_to_segment(b, c, d))
+        .min(distance_to_segment(c, a, b))
+        .min(distance_to_segment(d, a, b))
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let straddles = |p: f32, q: f32| (p > 0.0 && q < 0.0) || (p < 0.0 && q > 0.0);
+
+    straddles(cross(d - c, a - c), cross(d - c, b - c))
+        && straddles(cross(b - a, c - a), cross(b - a, d - a))
+}
+
+fn cross(u: Vector, v: Vector) -> f32 {
+    u.x * v.y - u.y * v.x
+}
+
+/// Whether `point` lies inside `polygon`, by the even-odd rule.
+pub fn contains(polygon: &[Point], point: Point) -> bool {
+    let mut inside = false;
+
+    for (index, a) in polygon.iter().enumerate() {
+        let b = polygon[(index + 1) % polygon.len()];
+
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+/// An affine transform, mapping `p` to `(a·x + c·y + e, b·x + d·y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+}
+
+impl Affine {
+    pub fn translate(offset: Vector) -> Self {
+        Self { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: offset.x, f: offset.y }
+    }
+
+    /// Scales uniformly by `factor` around `origin`.
+    pub fn scale(origin: Point, factor: f32) -> Self {
+        Self {
+            a: factor,
+            b: 0.0,
+            c: 0.0,
+            d: factor,
+            e: origin.x * (1.0 - factor),
+            f: origin.y * (1.0 - factor),
+        }
+    }
+
+    /// Rotates by `angle` radians around `origin`.
+    pub fn rotate(origin: Point, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            e: origin.x - cos * origin.x + sin * origin.y,
+            f: origin.y - sin * origin.x - cos * origin.y,
+        }
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(
+            self.a * point.x + self.c * point.y + self.e,
+            self.b * point.x + self.d * point.y + self.f,
+        )
+    }
+
+    /// How much lengths grow, used to scale stroke widths.
+    pub fn scale_factor(&self) -> f32 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+}
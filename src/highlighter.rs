@@ -0,0 +1,54 @@
+use iced::{Point, Vector};
+
+pub const OPACITY: f32 = 0.35;
+pub const WIDTH_SCALE: f32 = 2.5;
+
+const JOINT_SIDES: usize = 16;
+
+/// The area covered by a flat-capped highlighter stroke, as a set of
+/// polygons that all wind the same way.
+///
+/// Filled together with the non-zero rule, overlapping parts of the stroke
+/// are covered exactly once, so translucent ink never darkens where the
+/// stroke crosses itself.
+pub fn outline(points: &[Point], width: f32) -> Vec<Vec<Point>> {
+    let half = width / 2.0;
+
+    if let [point] = points {
+        return vec![vec![
+            *point + Vector::new(-half, half),
+            *point + Vector::new(half, half),
+            *point + Vector::new(half, -half),
+            *point + Vector::new(-half, -half),
+        ]];
+    }
+
+    let mut polygons = Vec::with_capacity(points.len() * 2);
+
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let direction = to - from;
+        let length = from.distance(to);
+
+        if length == 0.0 {
+            continue;
+        }
+
+        let normal = Vector::new(-direction.y, direction.x) * (half / length);
+
+        polygons.push(vec![from + normal, to + normal, to - normal, from - normal]);
+    }
+
+    for joint in points.iter().skip(1).take(points.len().saturating_sub(2)) {
+        polygons.push(
+            (0..JOINT_SIDES)
+                .map(|i| {
+                    let angle = -(i as f32) * std::f32::consts::TAU / JOINT_SIDES as f32;
+                    *joint + Vector::new(angle.cos(), angle.sin()) * half
+                })
+                .collect(),
+        );
+    }
+
+    polygons
+}
@@ -0,0 +1,329 @@
+use crate::document::{Document, Label, Stroke};
+use std::collections::HashSet;
+
+/// A reversible change to the document.
+#[derive(Debug, Clone)]
+pub enum Action {
+    AddStroke { index: usize, stroke: Stroke },
+    /// Strokes pasted or duplicated at their indices, in ascending order.
+    AddStrokes { strokes: Vec<(usize, Stroke)> },
+    Clear { strokes: Vec<Stroke>, labels: Vec<Label> },
+    /// Every stroke and label swapped for others, as when loading a session.
+    Replace {
+        before: (Vec<Stroke>, Vec<Label>),
+        after: (Vec<Stroke>, Vec<Label>),
+    },
+    /// Strokes removed from their original indices, and strokes inserted at
+    /// their final indices, both in ascending order.
+    Erase {
+        removed: Vec<(usize, Stroke)>,
+        inserted: Vec<(usize, Stroke)>,
+    },
+    /// Strokes moved, scaled, rotated or recolored in place.
+    EditStrokes {
+        before: Vec<(usize, Stroke)>,
+        after: Vec<(usize, Stroke)>,
+    },
+    AddLabel { index: usize, label: Label },
+    RemoveLabel { index: usize, label: Label },
+    /// A label moved or whose text was edited.
+    EditLabel { index: usize, before: Label, after: Label },
+}
+
+impl Action {
+    /// Describes the change from `before` to `after` as an erase, comparing
+    /// strokes by id. Returns `None` if nothing changed.
+    pub fn erase(before: &[Stroke], after: &[Stroke]) -> Option<Self> {
+        let before_ids: HashSet<u64> = before.iter().map(|stroke| stroke.id).collect();
+        let after_ids: HashSet<u64> = after.iter().map(|stroke| stroke.id).collect();
+
+        let removed: Vec<_> = before
+            .iter()
+            .enumerate()
+            .filter(|(_, stroke)| !after_ids.contains(&stroke.id))
+            .map(|(index, stroke)| (index, stroke.clone()))
+            .collect();
+
+        let inserted: Vec<_> = after
+            .iter()
+            .enumerate()
+            .filter(|(_, stroke)| !before_ids.contains(&stroke.id))
+            .map(|(index, stroke)| (index, stroke.clone()))
+            .collect();
+
+        if removed.is_empty() && inserted.is_empty() {
+            None
+        } else {
+            Some(Action::Erase { removed, inserted })
+        }
+    }
+
+    fn apply(&self, document: &mut Document) {
+        match self {
+            Action::AddStroke { index, stroke } => {
+                document.insert(*index, stroke.clone());
+            }
+            Action::AddStrokes { strokes } => {
+                swap(document, &[], strokes);
+            }
+            Action::Clear { .. } => {
+                document.take_strokes();
+                document.take_labels();
+            }
+            Action::Replace { after: (strokes, labels), .. } => {
+                document.restore_strokes(strokes.clone());
+                document.restore_labels(labels.clone());
+            }
+            Action::Erase { removed, inserted } => {
+                swap(document, removed, inserted);
+            }
+            Action::EditStrokes { after, .. } => {
+                replace(document, after);
+            }
+            Action::AddLabel { index, label } => {
+                document.insert_label(*index, label.clone());
+            }
+            Action::RemoveLabel { index, .. } => {
+                document.remove_label(*index);
+            }
+            Action::EditLabel { index, after, .. } => {
+                document.replace_label(*index, after.clone());
+            }
+        }
+    }
+
+    fn revert(&self, document: &mut Document) {
+        match self {
+            Action::AddStroke { index, .. } => {
+                document.remove(*index);
+            }
+            Action::AddStrokes { strokes } => {
+                swap(document, strokes, &[]);
+            }
+            Action::Clear { strokes, labels } => {
+                document.restore_strokes(strokes.clone());
+                document.restore_labels(labels.clone());
+            }
+            Action::Replace { before: (strokes, labels), .. } => {
+                document.restore_strokes(strokes.clone());
+                document.restore_labels(labels.clone());
+            }
+            Action::Erase { removed, inserted } => {
+                swap(document, inserted, removed);
+            }
+            Action::EditStrokes { before, .. } => {
+                replace(document, before);
+            }
+            Action::AddLabel { index, .. } => {
+                document.remove_label(*index);
+            }
+            Action::RemoveLabel { index, label } => {
+                document.insert_label(*index, label.clone());
+            }
+            Action::EditLabel { index, before, .. } => {
+                document.replace_label(*index, before.clone());
+            }
+        }
+    }
+}
+
+fn swap(document: &mut Document, remove: &[(usize, Stroke)], insert: &[(usize, Stroke)]) {
+    for (index, _) in remove.iter().rev() {
+        document.remove(*index);
+    }
+
+    for (index, stroke) in insert {
+        document.insert(*index, stroke.clone());
+    }
+}
+
+fn replace(document: &mut Document, strokes: &[(usize, Stroke)]) {
+    for (index, stroke) in strokes {
+        document.replace(*index, stroke.clone());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Action>,
+    redo: Vec<Action>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an action that has already been applied to the document.
+    pub fn record(&mut self, action: Action) {
+        self.undo.push(action);
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, document: &mut Document) -> bool {
+        match self.undo.pop() {
+            Some(action) => {
+                action.revert(document);
+                self.redo.push(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, document: &mut Document) -> bool {
+        match self.redo.pop() {
+            Some(action) => {
+                action.apply(document);
+                self.undo.push(action);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Tool;
+    use crate::eraser::Eraser;
+    use iced::{Color, Point};
+
+    /// Horizontal strokes from x = 0 to x = 100, one per `y`.
+    fn document(rows: &[f32]) -> Document {
+        let strokes = rows
+            .iter()
+            .map(|y| {
+                let mut stroke = Stroke::new(Point::new(0.0, *y), Color::BLACK, 2.0, Tool::Pen);
+                stroke.push(Point::new(50.0, *y));
+                stroke.push(Point::new(100.0, *y));
+                stroke
+            })
+            .collect();
+
+        let mut document = Document::new();
+        document.restore_strokes(strokes);
+        document
+    }
+
+    fn ids(document: &Document) -> Vec<u64> {
+        document.strokes().iter().map(|stroke| stroke.id).collect()
+    }
+
+    /// Erases from `from` to `to`, records it, and checks that undo and redo
+    /// go back and forth between the strokes before and after.
+    fn assert_round_trip(document: &mut Document, eraser: Eraser, from: Point, to: Point) {
+        let before = document.strokes().to_vec();
+
+        assert!(eraser.erase(document, from, to, 5.0));
+
+        let after = ids(document);
+        let mut history = History::new();
+        history.record(Action::erase(&before, document.strokes()).unwrap());
+
+        assert!(history.undo(document));
+        assert_eq!(ids(document), before.iter().map(|stroke| stroke.id).collect::<Vec<_>>());
+
+        assert!(history.redo(document));
+        assert_eq!(ids(document), after);
+    }
+
+    #[test]
+    fn precise_split_of_a_middle_stroke() {
+        let mut document = document(&[0.0, 20.0, 40.0]);
+        let [top, middle, bottom] = [0, 1, 2].map(|index| document.strokes()[index].id);
+
+        assert_round_trip(
+            &mut document,
+            Eraser::Precise,
+            Point::new(50.0, 15.0),
+            Point::new(50.0, 25.0),
+        );
+
+        // The middle stroke became two fragments in its place.
+        let strokes = document.strokes();
+
+        assert_eq!(strokes.len(), 4);
+        assert_eq!((strokes[0].id, strokes[3].id), (top, bottom));
+        assert!(strokes[1..3].iter().all(|stroke| stroke.id != middle));
+        assert!(strokes[1].points.iter().all(|point| point.x < 50.0));
+        assert!(strokes[2].points.iter().all(|point| point.x > 50.0));
+    }
+
+    #[test]
+    fn precise_erase_across_several_strokes() {
+        let mut document = document(&[10.0, 20.0, 30.0, 40.0]);
+
+        assert_round_trip(
+            &mut document,
+            Eraser::Precise,
+            Point::new(30.0, 16.0),
+            Point::new(70.0, 44.0),
+        );
+
+        // Each of the three crossed strokes was split in two.
+        assert_eq!(document.strokes().len(), 7);
+    }
+
+    #[test]
+    fn stroke_erase_across_several_strokes() {
+        let mut document = document(&[10.0, 20.0, 30.0, 40.0]);
+        let kept = [0, 3].map(|index| document.strokes()[index].id);
+
+        assert_round_trip(
+            &mut document,
+            Eraser::Stroke,
+            Point::new(50.0, 18.0),
+            Point::new(50.0, 32.0),
+        );
+
+        assert_eq!(ids(&document), kept);
+    }
+
+    #[test]
+    fn scattered_removal_round_trip() {
+        let mut document = document(&[10.0, 20.0, 30.0, 40.0, 50.0]);
+        let before = document.strokes().to_vec();
+
+        // As deleting a selection does, from the highest index down.
+        for index in [3, 1, 0] {
+            document.remove(index);
+        }
+
+        let after = ids(&document);
+        assert_eq!(after, [before[2].id, before[4].id]);
+
+        let mut history = History::new();
+        history.record(Action::erase(&before, document.strokes()).unwrap());
+
+        assert!(history.undo(&mut document));
+        assert_eq!(ids(&document), before.iter().map(|stroke| stroke.id).collect::<Vec<_>>());
+
+        assert!(history.redo(&mut document));
+        assert_eq!(ids(&document), after);
+    }
+
+    #[test]
+    fn added_strokes_round_trip() {
+        let mut document = document(&[10.0, 20.0]);
+        let before = ids(&document);
+        let added = self::document(&[30.0, 40.0]).strokes().to_vec();
+
+        // Pasted after the first stroke and at the end, in after-space.
+        let strokes = vec![(1, added[0].clone()), (3, added[1].clone())];
+        swap(&mut document, &[], &strokes);
+
+        let after = ids(&document);
+        assert_eq!(after, [before[0], added[0].id, before[1], added[1].id]);
+
+        let mut history = History::new();
+        history.record(Action::AddStrokes { strokes });
+
+        assert!(history.undo(&mut document));
+        assert_eq!(ids(&document), before);
+
+        assert!(history.redo(&mut document));
+        assert_eq!(ids(&document), after);
+    }
+}
@@ -0,0 +1,50 @@
+use iced::Point;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a point of the trail takes to fade out.
+pub const FADE: Duration = Duration::from_millis(1000);
+
+#[derive(Debug, Default)]
+pub struct Laser {
+    pub enabled: bool,
+    trail: VecDeque<(Point, Instant)>,
+}
+
+impl Laser {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.trail.clear();
+    }
+
+    pub fn push(&mut self, position: Point, now: Instant) {
+        if self.enabled {
+            self.trail.push_back((position, now));
+        }
+    }
+
+    pub fn prune(&mut self, now: Instant) {
+        while let Some((_, at)) = self.trail.front() {
+            if now.duration_since(*at) < FADE {
+                break;
+            }
+            self.trail.pop_front();
+        }
+    }
+
+    pub fn is_fading(&self) -> bool {
+        !self.trail.is_empty()
+    }
+
+    /// The segments of the trail with their opacity, oldest first.
+    pub fn trail(&self, now: Instant) -> impl Iterator<Item = (Point, Point, f32)> + '_ {
+        self.trail
+            .iter()
+            .zip(self.trail.iter().skip(1))
+            .map(move |((from, _), (to, at))| {
+                let age = now.saturating_duration_since(*at).as_secs_f32();
+
+                (*from, *to, (1.0 - age / FADE.as_secs_f32()).max(0.0))
+            })
+    }
+}
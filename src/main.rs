@@ -6,18 +6,67 @@ use iced::widget::canvas::{event, LineCap, LineJoin};
 use iced::widget::canvas::stroke::{self, Stroke};
 use iced::widget::canvas::{self, Canvas, Geometry};
 use iced::{
-    executor, touch, window, Application, Color, Command, Element, Length,
-    Point, Rectangle, Renderer, Settings, Subscription, Theme,
+    executor, touch, window, Application, Color, Command, Element, Font, Length,
+    Point, Rectangle, Renderer, Settings, Subscription, Theme, Vector,
 };
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use iced::application::{Appearance, StyleSheet};
 use iced::mouse::Event;
 
+mod board;
+mod clip;
+mod document;
+mod eraser;
+mod export;
+mod fading;
+mod geometry;
+mod highlighter;
+mod history;
+mod laser;
+mod palette;
+mod paper;
+mod png;
+mod recognizer;
+mod selection;
+mod session;
+mod shape;
+mod simplify;
+mod smoothing;
+mod spotlight;
+mod stabilizer;
+mod svg;
+mod text;
+mod visualizer;
+mod width;
+
+use board::{Board, Sheet};
+use document::{Document, Label, Tool};
+use eraser::Eraser;
+use geometry::Affine;
+use history::{Action, History};
+use laser::Laser;
+use palette::Palette;
+use paper::{Paper, Pattern};
+use selection::{Handle, Selection};
+use simplify::Simplify;
+use smoothing::{Outline, Segment, Smoothing};
+use spotlight::{Hole, Spotlight};
+use stabilizer::Stabilizer;
+use text::TextEdit;
+use visualizer::Visualizer;
+
 pub fn main() -> iced::Result {
     tracing_subscriber::fmt::init();
 
+    let session = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from(session::FILE), PathBuf::from);
+
     Painter::run(Settings {
+        flags: session,
         antialiasing: true,
         window: window::Settings {
             position: window::Position::Centered,
@@ -32,28 +81,709 @@ struct Painter {
     state: State,
 }
 
+/// An eraser drag, undone as a single action.
+#[derive(Debug)]
+struct EraseGesture {
+    eraser: Eraser,
+    last: Point,
+    before: Vec<document::Stroke>,
+}
+
+/// A label being dragged with the text tool; a click without moving it
+/// edits it instead.
+#[derive(Debug)]
+struct LabelDrag {
+    index: usize,
+    grab: Vector,
+    before: Label,
+    moved: bool,
+}
+
+/// A drag of the selection or one of its handles, undone as a single action.
+#[derive(Debug)]
+struct TransformGesture {
+    handle: Handle,
+    start: Point,
+    center: Point,
+    before: Vec<(usize, document::Stroke)>,
+    moved: bool,
+}
+
 #[derive(Debug)]
 struct State {
-    cache: canvas::Cache,
-    positions: Vec<Point>,
-    drawing: bool,
+    background_cache: canvas::Cache,
+    /// Committed strokes only; the active stroke is drawn on a fresh frame every redraw.
+    ink_cache: canvas::Cache,
+    board: Board,
+    document: Document,
+    history: History,
+    /// The ink of the boards not currently shown.
+    sheets: HashMap<Board, Sheet>,
+    /// Where Ctrl+S saves the session and Ctrl+O loads it from.
+    session: PathBuf,
+    palette: Palette,
+    paper: Paper,
+    width: f32,
+    width_changed_at: Option<Instant>,
+    tool: Tool,
+    /// Where the shape being dragged out was started.
+    anchor: Option<Point>,
+    cursor: Point,
+    /// Where and since when the cursor has been holding still while drawing.
+    still_at: Point,
+    still_since: Instant,
+    modifiers: keyboard::Modifiers,
+    eraser: Option<Eraser>,
+    erasing: Option<EraseGesture>,
+    laser: Laser,
+    text_tool: bool,
+    editing: Option<TextEdit>,
+    label_drag: Option<LabelDrag>,
+    select_tool: bool,
+    selection: Option<Selection>,
+    lasso: Option<Vec<Point>>,
+    transforming: Option<TransformGesture>,
+    disappearing_ink: bool,
+    fade_delays: fading::Delays,
+    /// When a fade delay was last changed, to show it briefly.
+    fade_delay_changed_at: Option<Instant>,
+    spotlight: Spotlight,
+    visualizer: Visualizer,
+    smoothing: Smoothing,
+    stabilizer: Stabilizer,
+    simplify: Simplify,
 }
 
 impl State {
     fn new() -> Self {
         Self {
-            cache: canvas::Cache::new(),
-            positions: Vec::new(),
-            drawing: false,
+            background_cache: canvas::Cache::new(),
+            ink_cache: canvas::Cache::new(),
+            board: Board::Overlay,
+            document: Document::new(),
+            history: History::new(),
+            sheets: HashMap::new(),
+            session: PathBuf::from(session::FILE),
+            palette: Palette::default(),
+            paper: Paper::default(),
+            width: 10.0,
+            width_changed_at: None,
+            tool: Tool::Pen,
+            anchor: None,
+            cursor: Point::ORIGIN,
+            still_at: Point::ORIGIN,
+            still_since: Instant::now(),
+            modifiers: keyboard::Modifiers::default(),
+            eraser: None,
+            erasing: None,
+            laser: Laser::default(),
+            text_tool: false,
+            editing: None,
+            label_drag: None,
+            select_tool: false,
+            selection: None,
+            lasso: None,
+            transforming: None,
+            disappearing_ink: false,
+            fade_delays: fading::Delays::default(),
+            fade_delay_changed_at: None,
+            spotlight: Spotlight::default(),
+            visualizer: Visualizer::default(),
+            smoothing: Smoothing::default(),
+            stabilizer: Stabilizer::default(),
+            simplify: Simplify::default(),
+        }
+    }
+
+    const FRAME: Duration = Duration::from_millis(16);
+    const WIDTH_INDICATOR: Duration = Duration::from_millis(800);
+    /// How long the fade delay badge stays up after the delay changes.
+    const FADE_DELAY_BADGE: Duration = Duration::from_millis(1500);
+    const HOLD_TO_SNAP: Duration = Duration::from_millis(500);
+    const STILL_RADIUS: f32 = 4.0;
+    /// How far duplicates are placed from the original.
+    const DUPLICATE_OFFSET: Vector = Vector::new(16.0, 16.0);
+
+    fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(width::MIN, width::MAX);
+        self.width_changed_at = Some(Instant::now());
+    }
+
+    fn is_animating(&self) -> bool {
+        self.width_changed_at.is_some()
+            || self.fade_delay_changed_at.is_some()
+            || self.laser.is_fading()
+            || !self.document.fading().is_empty()
+            || self.visualizer.is_animating()
+    }
+
+    fn tick(&mut self, now: Instant) {
+        self.laser.prune(now);
+        self.visualizer.prune(now);
+        self.document.expire(|stroke| fading::opacity(stroke, now) <= 0.0);
+
+        if let Some(changed_at) = self.width_changed_at {
+            if now.duration_since(changed_at) >= Self::WIDTH_INDICATOR {
+                self.width_changed_at = None;
+            }
+        }
+
+        if let Some(changed_at) = self.fade_delay_changed_at {
+            if now.duration_since(changed_at) >= Self::FADE_DELAY_BADGE {
+                self.fade_delay_changed_at = None;
+            }
+        }
+    }
+
+    fn new_stroke(&self, start: Point) -> document::Stroke {
+        let (color, width) = match self.tool {
+            Tool::Highlighter => (
+                Color { a: highlighter::OPACITY, ..self.palette.color() },
+                self.width * highlighter::WIDTH_SCALE,
+            ),
+            _ => (self.palette.color(), self.width),
+        };
+
+        let mut stroke = document::Stroke::new(start, color, width, self.tool);
+
+        if self.disappearing_ink {
+            stroke.fade_after = Some(self.fade_delays.get(self.tool));
+        }
+
+        stroke
+    }
+
+    fn eraser_radius(&self) -> f32 {
+        self.width.max(10.0)
+    }
+
+    fn begin_erase(&mut self, position: Point, eraser: Eraser) {
+        self.deselect();
+        self.commit_stroke();
+        self.end_erase();
+        self.erasing = Some(EraseGesture {
+            eraser,
+            last: position,
+            before: self.document.strokes().to_vec(),
+        });
+        self.erase_to(position);
+    }
+
+    fn erase_to(&mut self, position: Point) {
+        let radius = self.eraser_radius();
+
+        if let Some(gesture) = self.erasing.as_mut() {
+            if gesture.eraser.erase(&mut self.document, gesture.last, position, radius) {
+                self.ink_cache.clear();
+            }
+            gesture.last = position;
+        }
+    }
+
+    fn end_erase(&mut self) {
+        if let Some(gesture) = self.erasing.take() {
+            if let Some(action) = Action::erase(&gesture.before, self.document.strokes()) {
+                self.history.record(action);
+            }
+        }
+    }
+
+    fn update_shape(&mut self) {
+        let tool = self.document.active().map(|stroke| stroke.tool);
+
+        if let (Some(anchor), Some(tool)) = (self.anchor, tool) {
+            let cursor = self.paper.snap(self.cursor);
+            let points = shape::outline(tool, anchor, cursor, self.modifiers.shift());
+            self.document.reshape_stroke(tool, points);
+        }
+    }
+
+    fn track_stillness(&mut self, position: Point) {
+        if position.distance(self.still_at) > Self::STILL_RADIUS {
+            self.still_at = position;
+            self.still_since = Instant::now();
+        }
+    }
+
+    /// Replaces a freehand stroke with a clean shape if the pen was held
+    /// still before being lifted.
+    fn snap_if_held(&mut self) {
+        let Some(stroke) = self.document.active() else {
+            return;
+        };
+
+        if stroke.tool != Tool::Pen || self.still_since.elapsed() < Self::HOLD_TO_SNAP {
+            return;
+        }
+
+        if let Some(shape) = recognizer::recognize(&stroke.points) {
+            self.document.reshape_stroke(shape.tool, shape.points);
+        }
+    }
+
+    /// Shows `board`, or returns to the overlay if it is already shown.
+    fn toggle_board(&mut self, board: Board) {
+        let board = if self.board == board { Board::Overlay } else { board };
+
+        self.settle();
+
+        let shown = Sheet {
+            document: std::mem::take(&mut self.document),
+            history: std::mem::take(&mut self.history),
+        };
+        self.sheets.insert(self.board, shown);
+
+        let sheet = self.sheets.remove(&board).unwrap_or_default();
+        self.document = sheet.document;
+        self.history = sheet.history;
+        self.board = board;
+        self.ink_cache.clear();
+    }
+
+    /// Finishes whatever the pointer or keyboard is in the middle of.
+    fn settle(&mut self) {
+        self.end_erase();
+        self.commit_stroke();
+        self.end_label_drag();
+        self.commit_text();
+        self.deselect();
+    }
+
+    fn press_select(&mut self, position: Point) {
+        let handle = self
+            .selection
+            .as_ref()
+            .and_then(|selection| selection.handle_at(&self.document, position));
+
+        let handle = match handle {
+            Some(handle) => handle,
+            None => match selection::pick(&self.document, position) {
+                Some(index) => {
+                    self.selection = Some(Selection::single(index));
+                    Handle::Body
+                }
+                None => {
+                    self.selection = None;
+                    self.lasso = Some(vec![position]);
+                    return;
+                }
+            },
+        };
+
+        let Some(selection) = &self.selection else {
+            return;
+        };
+        let Some(bounds) = selection.bounds(&self.document) else {
+            return;
+        };
+
+        let before = selection
+            .indices()
+            .iter()
+            .map(|index| (*index, self.document.strokes()[*index].clone()))
+            .collect();
+
+        self.transforming = Some(TransformGesture {
+            handle,
+            start: position,
+            center: bounds.center(),
+            before,
+            moved: false,
+        });
+    }
+
+    fn drag_select(&mut self, position: Point) {
+        if let Some(lasso) = self.lasso.as_mut() {
+            lasso.push(position);
+        } else if let Some(gesture) = self.transforming.as_mut() {
+            let transform =
+                selection::drag(gesture.handle, gesture.center, gesture.start, position);
+
+            for (index, stroke) in &gesture.before {
+                let mut stroke = stroke.clone();
+                stroke.transform(&transform);
+                self.document.replace(*index, stroke);
+            }
+
+            gesture.moved = true;
+            self.ink_cache.clear();
+        }
+    }
+
+    fn release_select(&mut self) {
+        if let Some(lasso) = self.lasso.take() {
+            self.selection = Selection::lasso(&self.document, &lasso);
+        }
+
+        self.end_transform();
+    }
+
+    fn end_transform(&mut self) {
+        let Some(gesture) = self.transforming.take() else {
+            return;
+        };
+
+        if gesture.moved {
+            self.record_edit(gesture.before);
+        }
+    }
+
+    /// Records the change to the strokes at the indices of `before`.
+    fn record_edit(&mut self, before: Vec<(usize, document::Stroke)>) {
+        let after = before
+            .iter()
+            .map(|(index, _)| (*index, self.document.strokes()[*index].clone()))
+            .collect();
+
+        self.history.record(Action::EditStrokes { before, after });
+    }
+
+    fn deselect(&mut self) {
+        self.end_transform();
+        self.lasso = None;
+        self.selection = None;
+    }
+
+    fn delete_selection(&mut self) {
+        self.end_transform();
+
+        let Some(selection) = self.selection.take() else {
+            return;
+        };
+
+        let before = self.document.strokes().to_vec();
+
+        for index in selection.indices().iter().rev() {
+            self.document.remove(*index);
+        }
+
+        if let Some(action) = Action::erase(&before, self.document.strokes()) {
+            self.history.record(action);
+        }
+        self.ink_cache.clear();
+    }
+
+    fn selected_strokes(&self) -> Vec<document::Stroke> {
+        self.selection
+            .iter()
+            .flat_map(|selection| selection.indices())
+            .map(|index| self.document.strokes()[*index].clone())
+            .collect()
+    }
+
+    /// Adds `strokes` on top of the document as one undoable step, and
+    /// selects them so they can be moved into place.
+    fn add_strokes(&mut self, strokes: Vec<document::Stroke>, transform: Affine) {
+        self.settle();
+
+        if strokes.is_empty() {
+            return;
+        }
+
+        let first = self.document.strokes().len();
+        let mut added = Vec::new();
+
+        for (index, mut stroke) in (first..).zip(strokes) {
+            stroke.transform(&transform);
+            self.document.insert(index, stroke.clone());
+            added.push((index, stroke));
+        }
+
+        self.history.record(Action::AddStrokes { strokes: added });
+        self.selection = Some(Selection::new((first..self.document.strokes().len()).collect()));
+        self.select_tool = true;
+        self.text_tool = false;
+        self.eraser = None;
+        self.ink_cache.clear();
+    }
+
+    /// Adds `strokes` centered on the cursor.
+    fn paste(&mut self, strokes: Vec<document::Stroke>) {
+        let Some(bounds) = selection::bounds(&strokes) else {
+            return;
+        };
+
+        self.add_strokes(strokes, Affine::translate(self.cursor - bounds.center()));
+    }
+
+    fn duplicate(&mut self) {
+        let strokes = self
+            .selected_strokes()
+            .iter()
+            .map(document::Stroke::duplicate)
+            .collect();
+
+        self.add_strokes(strokes, Affine::translate(Self::DUPLICATE_OFFSET));
+    }
+
+    /// Writes the current board to an SVG file in the working directory.
+    fn export_svg(&mut self) {
+        self.settle();
+
+        let image = svg::export(&self.document, self.smoothing, self.board.background(), None);
+
+        match write_export(std::path::Path::new(""), "svg", image.as_bytes()) {
+            Ok(path) => println!("Exported {}", path.display()),
+            Err(error) => println!("Could not export SVG: {}", error),
+        }
+    }
+
+    /// Writes the current board to a PNG file in the working directory,
+    /// `scale` times its size on screen.
+    fn export_png(&mut self, scale: f32) {
+        self.settle();
+
+        let background = self.board.background();
+        let image = png::render(&self.document, self.smoothing, background, scale)
+            .and_then(|pixmap| pixmap.encode_png().ok());
+
+        match image.map(|image| write_export(std::path::Path::new(""), "png", &image)) {
+            Some(Ok(path)) => println!("Exported {}", path.display()),
+            Some(Err(error)) => println!("Could not export PNG: {}", error),
+            None => println!("Could not render PNG"),
         }
     }
+
+    fn save_session(&mut self) {
+        self.settle();
+
+        let path = self.session.as_path();
+
+        match session::save(path, self.board, &self.document, &self.sheets) {
+            Ok(()) => println!("Saved {}", path.display()),
+            Err(error) => println!("Could not save {}: {}", path.display(), error),
+        }
+    }
+
+    /// Replaces the ink of every board with the saved session, as a change
+    /// each board can undo.
+    fn load_session(&mut self) {
+        let mut loaded = match session::load(&self.session) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                println!("Could not load {}: {}", self.session.display(), error);
+                return;
+            }
+        };
+
+        self.settle();
+
+        let shown = Sheet {
+            document: std::mem::take(&mut self.document),
+            history: std::mem::take(&mut self.history),
+        };
+        self.sheets.insert(self.board, shown);
+
+        for board in Board::ALL {
+            let sheet = self.sheets.entry(board).or_default();
+            let mut saved = loaded.sheets.remove(&board).unwrap_or_default().document;
+
+            let before = (sheet.document.take_strokes(), sheet.document.take_labels());
+            let after = (saved.take_strokes(), saved.take_labels());
+
+            sheet.document.restore_strokes(after.0.clone());
+            sheet.document.restore_labels(after.1.clone());
+
+            let changed = [&before, &after]
+                .iter()
+                .any(|(strokes, labels)| !strokes.is_empty() || !labels.is_empty());
+
+            if changed {
+                sheet.history.record(Action::Replace { before, after });
+            }
+        }
+
+        let sheet = self.sheets.remove(&loaded.board).unwrap_or_default();
+        self.document = sheet.document;
+        self.history = sheet.history;
+        self.board = loaded.board;
+        self.ink_cache.clear();
+        println!("Loaded {}", self.session.display());
+    }
+
+    /// Gives the selected strokes the current color, keeping their opacity.
+    fn recolor_selection(&mut self) {
+        self.end_transform();
+
+        let Some(selection) = &self.selection else {
+            return;
+        };
+
+        let color = self.palette.color();
+        let mut before = Vec::new();
+
+        for index in selection.indices() {
+            let stroke = self.document.strokes()[*index].clone();
+            let recolored = document::Stroke {
+                color: Color { a: stroke.color.a, ..color },
+                ..stroke.clone()
+            };
+
+            self.document.replace(*index, recolored);
+            before.push((*index, stroke));
+        }
+
+        self.record_edit(before);
+        self.ink_cache.clear();
+    }
+
+    fn text_size(&self) -> f32 {
+        (self.width * 2.0).clamp(14.0, 96.0)
+    }
+
+    fn press_text(&mut self, position: Point) {
+        self.commit_text();
+
+        if let Some(index) = self.document.label_at(position) {
+            let label = self.document.labels()[index].clone();
+
+            self.label_drag = Some(LabelDrag {
+                index,
+                grab: position - label.position,
+                before: label,
+                moved: false,
+            });
+        } else {
+            let size = self.text_size();
+            let top_left = position - Vector::new(0.0, size * Label::LINE_HEIGHT / 2.0);
+
+            self.editing = Some(TextEdit::new(Label::new(top_left, self.palette.color(), size)));
+        }
+    }
+
+    fn drag_label(&mut self, position: Point) {
+        if let Some(drag) = self.label_drag.as_mut() {
+            let label = Label {
+                position: position - drag.grab,
+                ..drag.before.clone()
+            };
+
+            self.document.replace_label(drag.index, label);
+            self.ink_cache.clear();
+            drag.moved = true;
+        }
+    }
+
+    /// Records a label move, or returns the label that was clicked without
+    /// being moved.
+    fn end_label_drag(&mut self) -> Option<(usize, Label)> {
+        let drag = self.label_drag.take()?;
+
+        if !drag.moved {
+            return Some((drag.index, drag.before));
+        }
+
+        let after = self.document.labels()[drag.index].clone();
+        self.history.record(Action::EditLabel {
+            index: drag.index,
+            before: drag.before,
+            after,
+        });
+
+        None
+    }
+
+    fn commit_text(&mut self) {
+        let Some(edit) = self.editing.take() else {
+            return;
+        };
+
+        self.ink_cache.clear();
+
+        match edit.index {
+            None if edit.label.content.is_empty() => {}
+            None => {
+                let index = self.document.labels().len();
+                self.document.insert_label(index, edit.label.clone());
+                self.history.record(Action::AddLabel {
+                    index,
+                    label: edit.label,
+                });
+            }
+            Some(index) if edit.label.content.is_empty() => {
+                if let Some(label) = self.document.remove_label(index) {
+                    self.history.record(Action::RemoveLabel { index, label });
+                }
+            }
+            Some(index) => {
+                let after = edit.label;
+
+                if let Some(before) = self.document.replace_label(index, after.clone()) {
+                    if before != after {
+                        self.history.record(Action::EditLabel { index, before, after });
+                    }
+                }
+            }
+        }
+    }
+
+    fn commit_stroke(&mut self) {
+        self.anchor = None;
+
+        if let Some(index) = self.document.end_stroke(self.simplify) {
+            self.record_added(index);
+        }
+    }
+
+    fn record_added(&mut self, index: usize) {
+        let stroke = self.document.strokes()[index].clone();
+        self.history.record(Action::AddStroke { index, stroke });
+        self.ink_cache.clear();
+    }
 }
 
 #[derive(Debug)]
 enum Message {
     LeftButtonDown { position: Point },
     LeftButtonUp {},
+    RightButtonDown { position: Point },
+    RightButtonUp {},
     MouseDragged { position: Point },
+    ModifiersChanged { modifiers: keyboard::Modifiers },
+    Scrolled { lines: f32 },
+    WiderPen {},
+    NarrowerPen {},
+    Undo {},
+    Redo {},
+    ToggleTool { tool: Tool },
+    ToggleLaser {},
+    ToggleTextTool {},
+    ToggleSelectTool {},
+    DeleteSelection {},
+    Copy {},
+    Paste {},
+    Pasted { contents: Option<String> },
+    Duplicate {},
+    ExportSvg {},
+    ExportPng { scale: f32 },
+    SaveSession {},
+    LoadSession {},
+    TextInput { character: char },
+    TextKey { key: text::Key },
+    CommitText {},
+    ToggleDisappearingInk {},
+    ToggleBoard { board: Board },
+    NextPaperPattern {},
+    NextPaperSpacing {},
+    TogglePaperSnap {},
+    HideOverlay {},
+    ToggleVisualizer {},
+    ShowPointer { position: Point },
+    ShowClick { button: mouse::Button },
+    ShowKey { key_code: keyboard::KeyCode, modifiers: keyboard::Modifiers },
+    ToggleSpotlight {},
+    ToggleSpotlightHole {},
+    PinFadingStroke {},
+    CycleFadeDelay {},
+    CycleEraser {},
+    SelectColor { index: usize },
+    NextColor {},
+    PreviousColor {},
+    ToggleSmoothing {},
+    CycleSmoothingStrength {},
+    ToggleStabilizer {},
+    CycleStabilizerRadius {},
+    Tick { now: Instant },
     Reset {},
     Exit {},
 }
@@ -77,12 +807,12 @@ impl Application for Painter {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = PathBuf;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(session: PathBuf) -> (Self, Command<Message>) {
         (
             Painter {
-                state: State::new(),
+                state: State { session, ..State::new() },
             },
             Command::none(),
         )
@@ -94,9 +824,9 @@ impl Application for Painter {
 
     fn theme(&self) -> Theme {
         Theme::custom(iced::theme::Palette {
-            background: Color::TRANSPARENT,
+            background: self.state.board.background(),
             // background: Color::from_rgb(1.0, 0.0, 0.0),
-            text: Color::BLACK,
+            text: self.state.board.text_color(),
             primary: Color::from_rgb(0.5, 0.5, 0.0),
             success: Color::from_rgb(0.0, 1.0, 0.0),
             danger: Color::from_rgb(1.0, 0.0, 0.0),
@@ -107,24 +837,278 @@ impl Application for Painter {
         match message {
             Message::LeftButtonDown { position } => {
                 println!("Left button pressed at: {}, {}", position.x, position.y);
-                self.state.positions.push(position);
-                self.state.cache.clear();
-                self.state.drawing = true;
+                if let Some(eraser) = self.state.eraser {
+                    self.state.begin_erase(position, eraser);
+                    return Command::none();
+                }
+                if self.state.laser.enabled {
+                    return Command::none();
+                }
+                if self.state.text_tool {
+                    self.state.press_text(position);
+                    return Command::none();
+                }
+                if self.state.select_tool {
+                    self.state.press_select(position);
+                    return Command::none();
+                }
+                let start = if self.state.tool.is_shape() {
+                    self.state.paper.snap(position)
+                } else {
+                    position
+                };
+                let stroke = self.state.new_stroke(start);
+                self.state.commit_stroke();
+                self.state.stabilizer.start(position);
+                self.state.still_at = position;
+                self.state.still_since = Instant::now();
+                self.state.document.begin_stroke(stroke);
+                if self.state.tool.is_shape() {
+                    self.state.anchor = Some(start);
+                }
             }
             Message::MouseDragged { position } => {
-                if self.state.drawing {
-                    self.state.positions.push(position);
-                    self.state.cache.clear();
-                    println!("state.positions.size: {}", self.state.positions.len());
+                self.state.cursor = position;
+                self.state.laser.push(position, Instant::now());
+                if self.state.erasing.is_some() {
+                    self.state.erase_to(position);
+                } else if self.state.label_drag.is_some() {
+                    self.state.drag_label(position);
+                } else if self.state.lasso.is_some() || self.state.transforming.is_some() {
+                    self.state.drag_select(position);
+                } else if self.state.anchor.is_some() {
+                    self.state.update_shape();
+                } else if self.state.document.is_drawing() {
+                    self.state.track_stillness(position);
+                    if let Some(position) = self.state.stabilizer.follow(position) {
+                        self.state.document.extend_stroke(position);
+                    }
                 }
             }
             Message::LeftButtonUp { .. } => {
                 println!("Left button lifted");
-                self.state.drawing = false;
+                if let Some((index, label)) = self.state.end_label_drag() {
+                    self.state.editing = Some(TextEdit::edit(index, label));
+                    self.state.ink_cache.clear();
+                }
+                self.state.release_select();
+                self.state.end_erase();
+                self.state.snap_if_held();
+                self.state.commit_stroke();
+            }
+            Message::ModifiersChanged { modifiers } => {
+                self.state.modifiers = modifiers;
+                self.state.update_shape();
+            }
+            Message::RightButtonDown { position } => {
+                let eraser = self.state.eraser.unwrap_or(Eraser::Stroke);
+                self.state.begin_erase(position, eraser);
+            }
+            Message::RightButtonUp { .. } => {
+                self.state.end_erase();
+            }
+            Message::Scrolled { lines } => {
+                if self.state.spotlight.enabled {
+                    self.state.spotlight.scroll(lines);
+                } else {
+                    self.state.set_width(width::scroll(self.state.width, lines));
+                }
+            }
+            Message::WiderPen { .. } => {
+                self.state.set_width(width::next_preset(self.state.width));
+            }
+            Message::NarrowerPen { .. } => {
+                self.state.set_width(width::previous_preset(self.state.width));
+            }
+            Message::Undo { .. } => {
+                self.state.deselect();
+                self.state.end_erase();
+                self.state.end_label_drag();
+                self.state.commit_text();
+                // Disappearing ink is not in the history, so a wrong fading
+                // stroke is taken back before any permanent change.
+                if !self.state.document.cancel_stroke() && !self.state.document.drop_fading() {
+                    self.state.history.undo(&mut self.state.document);
+                    self.state.ink_cache.clear();
+                }
+            }
+            Message::Redo { .. } => {
+                self.state.deselect();
+                self.state.end_label_drag();
+                self.state.commit_text();
+                if !self.state.document.is_drawing() && self.state.erasing.is_none() {
+                    self.state.history.redo(&mut self.state.document);
+                    self.state.ink_cache.clear();
+                }
+            }
+            Message::ToggleTool { tool } => {
+                self.state.commit_text();
+                self.state.deselect();
+                self.state.tool = if self.state.tool == tool { Tool::Pen } else { tool };
+                self.state.eraser = None;
+                self.state.text_tool = false;
+                self.state.select_tool = false;
+            }
+            Message::ToggleTextTool { .. } => {
+                self.state.settle();
+                self.state.text_tool = !self.state.text_tool;
+                self.state.select_tool = false;
+                self.state.eraser = None;
+            }
+            Message::ToggleSelectTool { .. } => {
+                self.state.settle();
+                self.state.select_tool = !self.state.select_tool;
+                self.state.text_tool = false;
+                self.state.eraser = None;
+            }
+            Message::DeleteSelection { .. } => {
+                self.state.delete_selection();
+            }
+            Message::Copy { .. } => {
+                let strokes = self.state.selected_strokes();
+                if let Some(image) = clip::encode(&strokes, self.state.smoothing) {
+                    return iced::clipboard::write(image);
+                }
+            }
+            Message::Paste { .. } => {
+                return iced::clipboard::read(|contents| Message::Pasted { contents });
+            }
+            Message::Pasted { contents } => {
+                if let Some(strokes) = contents.as_deref().and_then(clip::decode) {
+                    self.state.paste(strokes);
+                }
+            }
+            Message::Duplicate { .. } => {
+                self.state.duplicate();
+            }
+            Message::ExportSvg { .. } => {
+                self.state.export_svg();
+            }
+            Message::ExportPng { scale } => {
+                self.state.export_png(scale);
+            }
+            Message::SaveSession { .. } => {
+                self.state.save_session();
+            }
+            Message::LoadSession { .. } => {
+                self.state.load_session();
+            }
+            Message::TextInput { character } => {
+                if let Some(edit) = self.state.editing.as_mut() {
+                    edit.insert(character);
+                }
+            }
+            Message::TextKey { key } => {
+                if let Some(edit) = self.state.editing.as_mut() {
+                    edit.apply(key);
+                }
+            }
+            Message::CommitText { .. } => {
+                self.state.commit_text();
+            }
+            Message::ToggleLaser { .. } => {
+                self.state.commit_stroke();
+                self.state.laser.toggle();
+            }
+            Message::ToggleDisappearingInk { .. } => {
+                self.state.disappearing_ink = !self.state.disappearing_ink;
+            }
+            Message::CycleFadeDelay { .. } => {
+                self.state.fade_delays.next(self.state.tool);
+                self.state.fade_delay_changed_at = Some(Instant::now());
+            }
+            Message::PinFadingStroke { .. } => {
+                // The fading stroke under the cursor, or else the latest one.
+                let fading = self.state.document.fading();
+                let index = fading
+                    .iter()
+                    .rposition(|stroke| selection::touches(stroke, self.state.cursor))
+                    .or(fading.len().checked_sub(1));
+
+                if let Some(index) = index.and_then(|index| self.state.document.pin_fading(index)) {
+                    self.state.record_added(index);
+                }
+            }
+            Message::ToggleBoard { board } => {
+                self.state.toggle_board(board);
+            }
+            Message::NextPaperPattern { .. } => {
+                self.state.paper.next_pattern();
+                self.state.background_cache.clear();
+            }
+            Message::NextPaperSpacing { .. } => {
+                self.state.paper.next_spacing();
+                self.state.background_cache.clear();
+            }
+            Message::TogglePaperSnap { .. } => {
+                self.state.paper.toggle_snap();
+            }
+            Message::HideOverlay { .. } => {
+                // iced 0.10 cannot let clicks through the window, so it gets
+                // out of the way instead; the ink is all still here when the
+                // window is restored.
+                self.state.settle();
+                return window::minimize(true);
+            }
+            Message::ToggleVisualizer { .. } => {
+                self.state.visualizer.toggle();
+            }
+            Message::ShowPointer { position } => {
+                self.state.visualizer.point(position);
+            }
+            Message::ShowClick { button } => {
+                self.state.visualizer.click(button, Instant::now());
+            }
+            Message::ShowKey { key_code, modifiers } => {
+                self.state.visualizer.key(key_code, modifiers, Instant::now());
+            }
+            Message::ToggleSpotlight { .. } => {
+                self.state.spotlight.toggle();
+            }
+            Message::ToggleSpotlightHole { .. } => {
+                self.state.spotlight.toggle_hole();
+            }
+            Message::CycleEraser { .. } => {
+                self.state.eraser = Eraser::cycle(self.state.eraser);
+            }
+            Message::SelectColor { index } => {
+                self.state.palette.select(index);
+                self.state.recolor_selection();
+            }
+            Message::NextColor { .. } => {
+                self.state.palette.next();
+                self.state.recolor_selection();
+            }
+            Message::PreviousColor { .. } => {
+                self.state.palette.previous();
+                self.state.recolor_selection();
+            }
+            Message::ToggleSmoothing { .. } => {
+                self.state.smoothing.toggle();
+                self.state.ink_cache.clear();
+            }
+            Message::CycleSmoothingStrength { .. } => {
+                self.state.smoothing.next_strength();
+                self.state.ink_cache.clear();
+            }
+            Message::ToggleStabilizer { .. } => {
+                self.state.stabilizer.toggle();
+            }
+            Message::CycleStabilizerRadius { .. } => {
+                self.state.stabilizer.next_radius();
+            }
+            Message::Tick { now } => {
+                self.state.tick(now);
             }
             Message::Reset { .. } => {
-                self.state.positions.clear();
-                self.state.cache.clear();
+                self.state.settle();
+                self.state.document.clear_fading();
+                let strokes = self.state.document.take_strokes();
+                let labels = self.state.document.take_labels();
+                if !strokes.is_empty() || !labels.is_empty() {
+                    self.state.history.record(Action::Clear { strokes, labels });
+                }
+                self.state.ink_cache.clear();
             }
             Message::Exit { .. } => {
                 std::process::exit(0);
@@ -135,7 +1119,17 @@ impl Application for Painter {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::none()
+        let mut subscriptions = Vec::new();
+
+        if self.state.is_animating() {
+            subscriptions.push(iced::time::every(State::FRAME).map(|now| Message::Tick { now }));
+        }
+
+        if self.state.visualizer.enabled {
+            subscriptions.push(iced::subscription::events_with(visualized));
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     fn view(&self) -> Element<Message> {
@@ -160,7 +1154,9 @@ impl canvas::Program<Message, Renderer> for State {
         match event {
             event::Event::Mouse(mouse_event) => match mouse_event {
                 mouse::Event::ButtonPressed(mouse::Button::Left) => {
-                    let position = cursor.position().unwrap();
+                    let Some(position) = cursor.position() else {
+                        return (event::Status::Ignored, None);
+                    };
                     (
                         event::Status::Captured,
                         Some(Message::LeftButtonDown { position }),
@@ -178,24 +1174,278 @@ impl canvas::Program<Message, Renderer> for State {
                         Some(Message::LeftButtonUp {}),
                     )
                 }
+                mouse::Event::ButtonPressed(mouse::Button::Right) => {
+                    let Some(position) = cursor.position() else {
+                        return (event::Status::Ignored, None);
+                    };
+                    (
+                        event::Status::Captured,
+                        Some(Message::RightButtonDown { position }),
+                    )
+                }
+                mouse::Event::ButtonReleased(mouse::Button::Right) => {
+                    (
+                        event::Status::Captured,
+                        Some(Message::RightButtonUp {}),
+                    )
+                }
+                mouse::Event::WheelScrolled { delta } => {
+                    let lines = match delta {
+                        mouse::ScrollDelta::Lines { y, .. } => y,
+                        mouse::ScrollDelta::Pixels { y, .. } => y / 20.0,
+                    };
+
+                    (
+                        event::Status::Captured,
+                        Some(Message::Scrolled { lines }),
+                    )
+                }
                 _ => (event::Status::Ignored, None),
             }
+            event::Event::Keyboard(keyboard_event) if self.editing.is_some() => {
+                (event::Status::Captured, text_input(keyboard_event))
+            }
             event::Event::Keyboard(keyboard_event) => match keyboard_event {
-                keyboard::Event::KeyPressed { key_code, .. } => match key_code {
+                keyboard::Event::KeyPressed { key_code, modifiers } => match key_code {
+                    keyboard::KeyCode::Z if modifiers.command() => {
+                        let message = if modifiers.shift() {
+                            Message::Redo {}
+                        } else {
+                            Message::Undo {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::Y if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::Redo {}),
+                        )
+                    }
+                    keyboard::KeyCode::C if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::Copy {}),
+                        )
+                    }
+                    keyboard::KeyCode::V if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::Paste {}),
+                        )
+                    }
+                    keyboard::KeyCode::S if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::SaveSession {}),
+                        )
+                    }
+                    keyboard::KeyCode::O if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::LoadSession {}),
+                        )
+                    }
+                    keyboard::KeyCode::E if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ExportSvg {}),
+                        )
+                    }
+                    keyboard::KeyCode::P if modifiers.command() => {
+                        let scale = if modifiers.shift() { 2.0 } else { 1.0 };
+
+                        (
+                            event::Status::Captured,
+                            Some(Message::ExportPng { scale }),
+                        )
+                    }
+                    keyboard::KeyCode::D if modifiers.command() => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::Duplicate {}),
+                        )
+                    }
                     keyboard::KeyCode::Escape => {
                         (
                             event::Status::Captured,
                             Some(Message::Exit {}),
                         )
                     }
+                    keyboard::KeyCode::RBracket => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::WiderPen {}),
+                        )
+                    }
+                    keyboard::KeyCode::LBracket => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::NarrowerPen {}),
+                        )
+                    }
+                    keyboard::KeyCode::H => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTool { tool: Tool::Highlighter }),
+                        )
+                    }
+                    keyboard::KeyCode::L => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTool { tool: Tool::Line }),
+                        )
+                    }
+                    keyboard::KeyCode::A => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTool { tool: Tool::Arrow }),
+                        )
+                    }
+                    keyboard::KeyCode::Q => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTool { tool: Tool::Rectangle }),
+                        )
+                    }
+                    keyboard::KeyCode::O => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTool { tool: Tool::Ellipse }),
+                        )
+                    }
+                    keyboard::KeyCode::T => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleTextTool {}),
+                        )
+                    }
+                    keyboard::KeyCode::V => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleSelectTool {}),
+                        )
+                    }
+                    keyboard::KeyCode::Delete | keyboard::KeyCode::Backspace => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::DeleteSelection {}),
+                        )
+                    }
+                    keyboard::KeyCode::D => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleLaser {}),
+                        )
+                    }
+                    keyboard::KeyCode::I => {
+                        let message = if modifiers.alt() {
+                            Message::CycleFadeDelay {}
+                        } else if modifiers.shift() {
+                            Message::PinFadingStroke {}
+                        } else {
+                            Message::ToggleDisappearingInk {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::F => {
+                        let message = if modifiers.shift() {
+                            Message::ToggleSpotlightHole {}
+                        } else {
+                            Message::ToggleSpotlight {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::W => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleBoard { board: Board::Whiteboard }),
+                        )
+                    }
+                    keyboard::KeyCode::K => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleBoard { board: Board::Blackboard }),
+                        )
+                    }
+                    keyboard::KeyCode::G => {
+                        let message = if modifiers.alt() {
+                            Message::NextPaperSpacing {}
+                        } else if modifiers.shift() {
+                            Message::TogglePaperSnap {}
+                        } else {
+                            Message::NextPaperPattern {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::P => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::HideOverlay {}),
+                        )
+                    }
+                    keyboard::KeyCode::F8 => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::ToggleVisualizer {}),
+                        )
+                    }
+                    keyboard::KeyCode::E => {
+                        (
+                            event::Status::Captured,
+                            Some(Message::CycleEraser {}),
+                        )
+                    }
+                    keyboard::KeyCode::C => {
+                        let message = if modifiers.shift() {
+                            Message::PreviousColor {}
+                        } else {
+                            Message::NextColor {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::S => {
+                        let message = if modifiers.shift() {
+                            Message::CycleSmoothingStrength {}
+                        } else {
+                            Message::ToggleSmoothing {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
+                    keyboard::KeyCode::B => {
+                        let message = if modifiers.shift() {
+                            Message::CycleStabilizerRadius {}
+                        } else {
+                            Message::ToggleStabilizer {}
+                        };
+
+                        (event::Status::Captured, Some(message))
+                    }
                     keyboard::KeyCode::R => {
                         (
                             event::Status::Captured,
                             Some(Message::Reset {}),
                         )
                     }
-                    _ => (event::Status::Ignored, None),
+                    _ => match color_index(key_code) {
+                        Some(index) => (
+                            event::Status::Captured,
+                            Some(Message::SelectColor { index }),
+                        ),
+                        None => (event::Status::Ignored, None),
+                    },
                 },
+                keyboard::Event::ModifiersChanged(modifiers) => {
+                    (
+                        event::Status::Captured,
+                        Some(Message::ModifiersChanged { modifiers }),
+                    )
+                }
                 _ => (event::Status::Ignored, None),
             }
             ,
@@ -209,40 +1459,710 @@ impl canvas::Program<Message, Renderer> for State {
         renderer: &Renderer,
         _theme: &Theme,
         bounds: Rectangle,
-        _cursor: mouse::Cursor,
+        cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
+        let mut layers = Vec::new();
 
-        let path_shape = self.cache.draw(renderer, bounds.size(), |frame| {
+        if let Some(pattern) = self.paper.pattern {
+            layers.push(self.background_cache.draw(renderer, bounds.size(), |frame| {
+                draw_paper(frame, pattern, self.paper.spacing);
+            }));
+        }
 
-            if self.positions.len() < 2 {
-                return;
+        if self.spotlight.enabled {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            draw_spotlight(&mut frame, &self.spotlight, cursor.position());
+            layers.push(frame.into_geometry());
+        }
+
+        let ink = self.ink_cache.draw(renderer, bounds.size(), |frame| {
+            let strokes = self.document.strokes();
+            let (highlights, pens): (Vec<_>, Vec<_>) = strokes
+                .iter()
+                .partition(|stroke| stroke.tool == Tool::Highlighter);
+
+            for stroke in highlights.into_iter().chain(pens) {
+                draw_stroke(frame, stroke, self.smoothing, 1.0);
+            }
+
+            let edited = self.editing.as_ref().and_then(|edit| edit.index);
+
+            for (index, label) in self.document.labels().iter().enumerate() {
+                if Some(index) != edited {
+                    draw_label(frame, label);
+                }
             }
+        });
+
+        let mut live = canvas::Frame::new(renderer, bounds.size());
+        let now = Instant::now();
+
+        for stroke in self.document.fading() {
+            draw_stroke(&mut live, stroke, self.smoothing, fading::opacity(stroke, now));
+        }
 
-            let mut builder = canvas::path::Builder::new();
+        if let Some(stroke) = self.document.active() {
+            draw_stroke(&mut live, stroke, self.smoothing, 1.0);
+
+            if self.stabilizer.enabled {
+                draw_stabilizer(&mut live, &self.stabilizer, cursor.position());
+            }
+        }
+
+        if let Some(edit) = &self.editing {
+            draw_label(&mut live, &edit.label);
+            draw_caret(&mut live, edit);
+        }
+
+        if let Some(lasso) = &self.lasso {
+            draw_lasso(&mut live, lasso);
+        }
+
+        let selected = self.selection.as_ref();
+
+        if let Some(bounds) = selected.and_then(|selection| selection.bounds(&self.document)) {
+            draw_selection(&mut live, bounds);
+        }
+
+        if self.laser.enabled {
+            draw_laser(&mut live, &self.laser, cursor.position(), self.palette.color());
+        }
+
+        if self.visualizer.enabled {
+            draw_visualizer(&mut live, &self.visualizer, now);
+        }
+
+        if self.fade_delay_changed_at.is_some() {
+            let delay = self.fade_delays.get(self.tool);
+            let kind = match self.tool {
+                Tool::Pen => "Pen",
+                Tool::Highlighter => "Highlighter",
+                _ => "Shapes",
+            };
+
+            draw_badge(&mut live, &format!("{} fades after {}s", kind, delay.as_secs()));
+        }
+
+        let erasing = self.eraser.is_some() || self.erasing.is_some();
+
+        if let Some(position) = cursor.position().filter(|_| erasing) {
+            draw_eraser(&mut live, position, self.eraser_radius());
+        }
+
+        if let Some(position) = cursor.position().filter(|_| self.width_changed_at.is_some()) {
+            draw_width_indicator(&mut live, position, self.width, self.palette.color());
+        }
+
+        layers.push(ink);
+        layers.push(live.into_geometry());
+
+        layers
+    }
+}
 
-            for (index, p) in self.positions.iter().enumerate() {
-                let p = Point::new(p.x, p.y);
+/// Writes an export to a new file in `directory`, named after the time;
+/// exports within the same second get a numbered suffix rather than
+/// overwriting each other.
+fn write_export(
+    directory: &std::path::Path,
+    extension: &str,
+    contents: &[u8],
+) -> std::io::Result<PathBuf> {
+    use std::io::Write;
 
-                match index {
-                    0 => builder.move_to(p),
-                    _ => builder.line_to(p),
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    for attempt in 0.. {
+        let path = match attempt {
+            0 => directory.join(format!("vivopaint-{seconds}.{extension}")),
+            _ => directory.join(format!("vivopaint-{seconds}-{attempt}.{extension}")),
+        };
+
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => return file.write_all(contents).map(|()| path),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    unreachable!("ran out of export file names")
+}
+
+/// Routes key presses to the label being typed, so tool shortcuts don't fire.
+fn text_input(event: keyboard::Event) -> Option<Message> {
+    use keyboard::KeyCode;
+
+    match event {
+        keyboard::Event::CharacterReceived(character) if !character.is_control() => {
+            Some(Message::TextInput { character })
+        }
+        keyboard::Event::KeyPressed { key_code, .. } => {
+            let key = match key_code {
+                KeyCode::Enter | KeyCode::Escape => return Some(Message::CommitText {}),
+                KeyCode::Backspace => text::Key::Backspace,
+                KeyCode::Delete => text::Key::Delete,
+                KeyCode::Left => text::Key::Left,
+                KeyCode::Right => text::Key::Right,
+                KeyCode::Home => text::Key::Home,
+                KeyCode::End => text::Key::End,
+                _ => return None,
+            };
+
+            Some(Message::TextKey { key })
+        }
+        keyboard::Event::ModifiersChanged(modifiers) => {
+            Some(Message::ModifiersChanged { modifiers })
+        }
+        _ => None,
+    }
+}
+
+/// Feeds every pointer move, click and key press to the visualizer, whether
+/// or not the canvas used it.
+fn visualized(event: iced::Event, _status: iced::event::Status) -> Option<Message> {
+    match event {
+        iced::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+            Some(Message::ShowPointer { position })
+        }
+        iced::Event::Mouse(mouse::Event::ButtonPressed(button)) => {
+            Some(Message::ShowClick { button })
+        }
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) => {
+            Some(Message::ShowKey { key_code, modifiers })
+        }
+        _ => None,
+    }
+}
+
+fn color_index(key_code: keyboard::KeyCode) -> Option<usize> {
+    use keyboard::KeyCode;
+
+    let keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+
+    keys.iter().position(|key| *key == key_code)
+}
+
+fn draw_stroke(
+    frame: &mut canvas::Frame,
+    stroke: &document::Stroke,
+    smoothing: Smoothing,
+    opacity: f32,
+) {
+    let color = Color {
+        a: stroke.color.a * opacity,
+        ..stroke.color
+    };
+
+    if stroke.tool == Tool::Highlighter {
+        draw_highlight(frame, stroke, color);
+        return;
+    }
+
+    let (start, segments, arrow_head) = match smoothing::outline(stroke, smoothing) {
+        None => return,
+        Some(Outline::Dot { center, radius }) => {
+            frame.fill(&canvas::Path::circle(center, radius), color);
+            return;
+        }
+        Some(Outline::Path { start, segments, arrow_head }) => (start, segments, arrow_head),
+    };
+
+    let mut builder = canvas::path::Builder::new();
+    builder.move_to(start);
+
+    for segment in segments {
+        match segment {
+            Segment::Line(to) => builder.line_to(to),
+            Segment::Cubic { control_a, control_b, to } => {
+                builder.bezier_curve_to(control_a, control_b, to)
+            }
+        }
+    }
+
+    if let Some([left, tip, right]) = arrow_head {
+        builder.move_to(left);
+        builder.line_to(tip);
+        builder.line_to(right);
+    }
+
+    let path = builder.build();
+
+    frame.stroke(
+        &path,
+        Stroke {
+            style: stroke::Style::Solid(color),
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
+            width: stroke.width,
+            ..Stroke::default()
+        },
+    );
+}
+
+fn draw_highlight(frame: &mut canvas::Frame, stroke: &document::Stroke, color: Color) {
+    let path = canvas::Path::new(|builder| {
+        for polygon in highlighter::outline(&stroke.points, stroke.width) {
+            if let Some((first, rest)) = polygon.split_first() {
+                builder.move_to(*first);
+
+                for point in rest {
+                    builder.line_to(*point);
                 }
+
+                builder.close();
             }
+        }
+    });
 
-            let path = builder.build();
+    frame.fill(&path, color);
+}
 
-            frame.stroke(
-                &path,
-                Stroke {
-                    style: stroke::Style::Solid(Color::from_rgba(1.0, 0.0, 0.0, 0.5)),
-                    line_cap: LineCap::Round,
-                    line_join: LineJoin::Round,
-                    width: 10.0,
-                    ..Stroke::default()
-                },
-            );
+fn draw_stabilizer(frame: &mut canvas::Frame, stabilizer: &Stabilizer, cursor: Option<Point>) {
+    let guide = Stroke::default()
+        .with_color(Color::from_rgba(0.5, 0.5, 0.5, 0.8))
+        .with_width(1.0);
+
+    frame.stroke(&canvas::Path::circle(stabilizer.brush(), stabilizer.radius), guide.clone());
+
+    if let Some(cursor) = cursor {
+        frame.stroke(&canvas::Path::line(stabilizer.brush(), cursor), guide);
+    }
+}
+
+fn draw_paper(frame: &mut canvas::Frame, pattern: Pattern, spacing: f32) {
+    let color = Color::from_rgba(0.5, 0.5, 0.5, 0.35);
+    let size = frame.size();
+    let columns = (size.width / spacing).ceil() as usize;
+    let rows = (size.height / spacing).ceil() as usize;
+
+    match pattern {
+        Pattern::Grid | Pattern::Lines => {
+            let path = canvas::Path::new(|builder| {
+                for row in 0..=rows {
+                    let y = row as f32 * spacing;
+                    builder.move_to(Point::new(0.0, y));
+                    builder.line_to(Point::new(size.width, y));
+                }
+
+                if pattern == Pattern::Grid {
+                    for column in 0..=columns {
+                        let x = column as f32 * spacing;
+                        builder.move_to(Point::new(x, 0.0));
+                        builder.line_to(Point::new(x, size.height));
+                    }
+                }
+            });
+
+            frame.stroke(&path, Stroke::default().with_color(color).with_width(1.0));
+        }
+        Pattern::Dots => {
+            let path = canvas::Path::new(|builder| {
+                for row in 0..=rows {
+                    for column in 0..=columns {
+                        let center = Point::new(column as f32 * spacing, row as f32 * spacing);
+                        builder.circle(center, 1.5);
+                    }
+                }
+            });
+
+            frame.fill(&path, Color { a: 0.7, ..color });
+        }
+    }
+}
+
+fn draw_spotlight(frame: &mut canvas::Frame, spotlight: &Spotlight, cursor: Option<Point>) {
+    let bounds = frame.size();
+
+    let path = canvas::Path::new(|builder| {
+        builder.rectangle(Point::ORIGIN, bounds);
+
+        if let Some(center) = cursor {
+            match spotlight.hole {
+                Hole::Circle => builder.circle(center, spotlight.radius),
+                Hole::Rectangle => {
+                    let (top_left, size) = spotlight.rectangle(center);
+                    builder.rectangle(top_left, size);
+                }
+            }
+        }
+    });
+
+    frame.fill(
+        &path,
+        canvas::Fill {
+            style: stroke::Style::Solid(spotlight::DIM),
+            rule: canvas::fill::Rule::EvenOdd,
+        },
+    );
+}
+
+fn draw_visualizer(frame: &mut canvas::Frame, visualizer: &Visualizer, now: Instant) {
+    const KEY_SIZE: f32 = 22.0;
+    const MARGIN: f32 = 16.0;
+
+    for (ripple, progress) in visualizer.ripples(now) {
+        let color = match ripple.button {
+            mouse::Button::Right => Color::from_rgb(0.2, 0.5, 1.0),
+            _ => Color::from_rgb(1.0, 0.6, 0.0),
+        };
+
+        frame.stroke(
+            &canvas::Path::circle(ripple.position, 8.0 + 32.0 * progress),
+            Stroke::default()
+                .with_color(Color { a: 1.0 - progress, ..color })
+                .with_width(3.0),
+        );
+    }
+
+    let keys: Vec<_> = visualizer.keys(now).collect();
+    let line_height = KEY_SIZE * 1.5;
+    let mut y = frame.height() - MARGIN - line_height * keys.len() as f32;
+
+    for (label, opacity) in keys {
+        let width = label.chars().count() as f32 * KEY_SIZE * 0.6 + KEY_SIZE;
+
+        frame.fill_rectangle(
+            Point::new(MARGIN, y),
+            Size::new(width, line_height - 4.0),
+            Color::from_rgba(0.0, 0.0, 0.0, 0.6 * opacity),
+        );
+        frame.fill_text(canvas::Text {
+            content: label.to_string(),
+            position: Point::new(MARGIN + KEY_SIZE / 2.0, y + 4.0),
+            color: Color { a: opacity, ..Color::WHITE },
+            size: KEY_SIZE,
+            ..canvas::Text::default()
         });
 
-        vec![path_shape]
+        y += line_height;
+    }
+}
+
+fn draw_label(frame: &mut canvas::Frame, label: &Label) {
+    frame.fill_text(canvas::Text {
+        content: label.content.clone(),
+        position: label.position,
+        color: label.color,
+        size: label.size,
+        font: Font::MONOSPACE,
+        ..canvas::Text::default()
+    });
+}
+
+fn draw_caret(frame: &mut canvas::Frame, edit: &TextEdit) {
+    let label = &edit.label;
+    let x = label.position.x + edit.caret as f32 * label.size * Label::ADVANCE;
+    let top = Point::new(x, label.position.y);
+    let bottom = Point::new(x, label.position.y + label.size * Label::LINE_HEIGHT);
+
+    frame.stroke(
+        &canvas::Path::line(top, bottom),
+        Stroke::default().with_color(label.color).with_width(2.0),
+    );
+}
+
+fn draw_lasso(frame: &mut canvas::Frame, lasso: &[Point]) {
+    let Some((first, rest)) = lasso.split_first() else {
+        return;
+    };
+
+    let mut builder = canvas::path::Builder::new();
+    builder.move_to(*first);
+
+    for point in rest {
+        builder.line_to(*point);
+    }
+
+    frame.stroke(
+        &builder.build(),
+        Stroke::default()
+            .with_color(Color::from_rgba(0.5, 0.5, 0.5, 0.8))
+            .with_width(1.0),
+    );
+}
+
+fn draw_selection(frame: &mut canvas::Frame, bounds: Rectangle) {
+    let outline = Stroke::default()
+        .with_color(Color::from_rgba(0.2, 0.5, 1.0, 0.9))
+        .with_width(1.0);
+    let top = Point::new(bounds.center_x(), bounds.y);
+    let rotate = selection::rotate_handle(bounds);
+
+    frame.stroke(&canvas::Path::rectangle(bounds.position(), bounds.size()), outline.clone());
+    frame.stroke(&canvas::Path::line(top, rotate), outline.clone());
+
+    let size = selection::HANDLE_SIZE;
+
+    for corner in selection::corners(bounds) {
+        let handle = canvas::Path::rectangle(
+            corner - Vector::new(size, size),
+            Size::new(size * 2.0, size * 2.0),
+        );
+
+        frame.fill(&handle, Color::WHITE);
+        frame.stroke(&handle, outline.clone());
+    }
+
+    let handle = canvas::Path::circle(rotate, size);
+
+    frame.fill(&handle, Color::WHITE);
+    frame.stroke(&handle, outline);
+}
+
+fn draw_badge(frame: &mut canvas::Frame, label: &str) {
+    const SIZE: f32 = 16.0;
+    const MARGIN: f32 = 12.0;
+
+    let width = label.chars().count() as f32 * SIZE * 0.6 + SIZE;
+    let top_left = Point::new(frame.width() - MARGIN - width, MARGIN);
+
+    frame.fill_rectangle(
+        top_left,
+        Size::new(width, SIZE * 1.6),
+        Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+    );
+    frame.fill_text(canvas::Text {
+        content: label.to_string(),
+        position: Point::new(top_left.x + SIZE / 2.0, top_left.y + SIZE * 0.3),
+        color: Color::WHITE,
+        size: SIZE,
+        ..canvas::Text::default()
+    });
+}
+
+fn draw_laser(frame: &mut canvas::Frame, laser: &Laser, cursor: Option<Point>, color: Color) {
+    const WIDTH: f32 = 6.0;
+
+    for (from, to, opacity) in laser.trail(Instant::now()) {
+        frame.stroke(
+            &canvas::Path::line(from, to),
+            Stroke {
+                style: stroke::Style::Solid(Color { a: opacity, ..color }),
+                line_cap: LineCap::Round,
+                width: WIDTH * opacity,
+                ..Stroke::default()
+            },
+        );
+    }
+
+    if let Some(position) = cursor {
+        for (radius, opacity) in [(WIDTH * 2.5, 0.15), (WIDTH * 1.6, 0.35), (WIDTH, 1.0)] {
+            frame.fill(&canvas::Path::circle(position, radius), Color { a: opacity, ..color });
+        }
+    }
+}
+
+fn draw_eraser(frame: &mut canvas::Frame, position: Point, radius: f32) {
+    frame.stroke(
+        &canvas::Path::circle(position, radius),
+        Stroke::default()
+            .with_color(Color::from_rgba(0.5, 0.5, 0.5, 0.8))
+            .with_width(1.0),
+    );
+}
+
+fn draw_width_indicator(frame: &mut canvas::Frame, position: Point, width: f32, color: Color) {
+    let circle = canvas::Path::circle(position, width / 2.0);
+
+    frame.fill(&circle, Color { a: 0.3, ..color });
+    frame.stroke(
+        &circle,
+        Stroke::default()
+            .with_color(Color::from_rgba(0.5, 0.5, 0.5, 0.8))
+            .with_width(1.0),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use canvas::Program;
+
+    const FRAMES: usize = 200;
+
+    fn renderer() -> Renderer {
+        Renderer::TinySkia(iced_tiny_skia::Renderer::new(iced_tiny_skia::Backend::new(
+            iced_tiny_skia::Settings::default(),
+        )))
+    }
+
+    /// A state with `count` committed freehand strokes of 100 points each.
+    fn state_with_strokes(count: usize) -> State {
+        let mut state = State::new();
+
+        for i in 0..count {
+            let y = (i % 500) as f32 + 10.0;
+            let mut stroke = state.new_stroke(Point::new(10.0, y));
+
+            for x in 1..100 {
+                stroke.push(Point::new(10.0 + x as f32 * 8.0, y + (x as f32 * 0.3).sin() * 6.0));
+            }
+
+            state.document.begin_stroke(stroke);
+            state.commit_stroke();
+        }
+
+        state
+    }
+
+    /// Average time to build one frame while a stroke is being drawn over
+    /// `count` committed strokes, as on every `MouseDragged`.
+    fn frame_time(count: usize) -> Duration {
+        let mut state = state_with_strokes(count);
+        let renderer = renderer();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(1000.0, 600.0));
+        let cursor = mouse::Cursor::Unavailable;
+
+        state.document.begin_stroke(state.new_stroke(Point::new(500.0, 300.0)));
+        state.draw(&(), &renderer, &Theme::Light, bounds, cursor);
+
+        let started = Instant::now();
+
+        for frame in 0..FRAMES {
+            let angle = frame as f32 * 0.1;
+            state.document.extend_stroke(Point::new(
+                500.0 + angle.cos() * frame as f32,
+                300.0 + angle.sin() * frame as f32,
+            ));
+            state.draw(&(), &renderer, &Theme::Light, bounds, cursor);
+        }
+
+        started.elapsed() / FRAMES as u32
+    }
+
+    /// Time to draw `count` committed strokes from scratch, as every frame
+    /// did before they were cached.
+    fn rebuild_time(count: usize) -> Duration {
+        let state = state_with_strokes(count);
+        let renderer = renderer();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(1000.0, 600.0));
+
+        let started = Instant::now();
+        state.draw(&(), &renderer, &Theme::Light, bounds, mouse::Cursor::Unavailable);
+
+        started.elapsed()
+    }
+
+    /// The committed ink of a frame drawn without paper or spotlight, which
+    /// is the primitive kept by the ink cache.
+    fn ink_layer(layers: &[Geometry]) -> std::sync::Arc<iced_tiny_skia::Primitive> {
+        match layers.first() {
+            Some(Geometry::TinySkia(iced_tiny_skia::Primitive::Cache { content })) => content.clone(),
+            _ => panic!("no cached ink layer"),
+        }
+    }
+
+    #[test]
+    fn drawing_a_stroke_keeps_the_ink_cache() {
+        let mut state = state_with_strokes(10);
+        let renderer = renderer();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(1000.0, 600.0));
+        let cursor = mouse::Cursor::Unavailable;
+
+        state.document.begin_stroke(state.new_stroke(Point::new(500.0, 300.0)));
+        let cached = ink_layer(&state.draw(&(), &renderer, &Theme::Light, bounds, cursor));
+
+        for x in 1..20 {
+            state.document.extend_stroke(Point::new(500.0 + x as f32 * 5.0, 300.0));
+            let ink = ink_layer(&state.draw(&(), &renderer, &Theme::Light, bounds, cursor));
+
+            assert!(std::sync::Arc::ptr_eq(&cached, &ink));
+        }
+
+        state.commit_stroke();
+        let ink = ink_layer(&state.draw(&(), &renderer, &Theme::Light, bounds, cursor));
+
+        assert!(!std::sync::Arc::ptr_eq(&cached, &ink));
+    }
+
+    /// Wall-clock timing, only meaningful in an optimized build:
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing benchmark; run with --release"]
+    fn frame_time_does_not_grow_with_committed_strokes() {
+        let few = frame_time(10);
+        let many = frame_time(1_000);
+
+        println!("frame with 10 committed strokes: {:?}", few);
+        println!("frame with 1000 committed strokes: {:?}", many);
+        println!("rebuilding 1000 committed strokes: {:?}", rebuild_time(1_000));
+
+        assert!(
+            many < few * 3 + Duration::from_millis(1),
+            "{:?} with 1000 strokes against {:?} with 10",
+            many,
+            few
+        );
+    }
+
+    #[test]
+    fn loading_a_session_can_be_undone() {
+        let mut state = state_with_strokes(3);
+        state.session = std::env::temp_dir()
+            .join(format!("vivopaint-load-undo-{}.json", std::process::id()));
+
+        state.save_session();
+
+        let mut stroke = state.new_stroke(Point::new(50.0, 50.0));
+        stroke.push(Point::new(90.0, 90.0));
+        state.document.begin_stroke(stroke);
+        state.commit_stroke();
+
+        state.load_session();
+        let _ = std::fs::remove_file(&state.session);
+
+        assert_eq!(state.document.strokes().len(), 3);
+        assert!(state.history.undo(&mut state.document));
+        assert_eq!(state.document.strokes().len(), 4);
+    }
+
+    #[test]
+    fn undo_takes_back_fading_ink_first() {
+        let mut painter = Painter { state: state_with_strokes(2) };
+        let _ = painter.update(Message::Reset {});
+        let _ = painter.update(Message::ToggleDisappearingInk {});
+
+        let mut stroke = painter.state.new_stroke(Point::new(50.0, 50.0));
+        stroke.push(Point::new(90.0, 90.0));
+        painter.state.document.begin_stroke(stroke);
+        painter.state.commit_stroke();
+
+        assert_eq!(painter.state.document.fading().len(), 1);
+
+        let _ = painter.update(Message::Undo {});
+
+        assert!(painter.state.document.fading().is_empty());
+        assert!(painter.state.document.strokes().is_empty());
+
+        let _ = painter.update(Message::Undo {});
+
+        assert_eq!(painter.state.document.strokes().len(), 2);
+    }
+
+    #[test]
+    fn exports_in_the_same_second_do_not_overwrite_each_other() {
+        let directory =
+            std::env::temp_dir().join(format!("vivopaint-exports-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let first = write_export(&directory, "svg", b"first").unwrap();
+        let second = write_export(&directory, "svg", b"second").unwrap();
+        let contents = [&first, &second].map(|path| std::fs::read(path).unwrap());
+        let _ = std::fs::remove_dir_all(&directory);
+
+        assert_ne!(first, second);
+        assert_eq!(contents, [b"first".to_vec(), b"second".to_vec()]);
     }
 }
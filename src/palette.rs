@@ -0,0 +1,43 @@
+use iced::Color;
+
+const fn rgb(r: f32, g: f32, b: f32) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
+
+/// Preset pen colors, selected with the number keys `1`–`9`.
+pub const PRESETS: [Color; 9] = [
+    rgb(0.90, 0.10, 0.10), // red
+    rgb(0.10, 0.70, 0.20), // green
+    rgb(0.10, 0.35, 0.90), // blue
+    rgb(1.00, 0.85, 0.00), // yellow
+    rgb(0.00, 0.00, 0.00), // black
+    rgb(1.00, 1.00, 1.00), // white
+    rgb(1.00, 0.50, 0.00), // orange
+    rgb(0.60, 0.20, 0.80), // purple
+    rgb(0.00, 0.80, 0.85), // cyan
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Palette {
+    index: usize,
+}
+
+impl Palette {
+    pub fn color(&self) -> Color {
+        PRESETS[self.index]
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < PRESETS.len() {
+            self.index = index;
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % PRESETS.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.index = (self.index + PRESETS.len() - 1) % PRESETS.len();
+    }
+}
@@ -0,0 +1,67 @@
+use iced::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Grid,
+    Dots,
+    Lines,
+}
+
+/// The ruling drawn behind the ink, which shapes can snap to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paper {
+    pub pattern: Option<Pattern>,
+    pub spacing: f32,
+    pub snap: bool,
+}
+
+impl Paper {
+    pub const SPACINGS: [f32; 4] = [16.0, 24.0, 32.0, 48.0];
+
+    /// Cycles through no ruling, a grid, dots and ruled lines.
+    pub fn next_pattern(&mut self) {
+        self.pattern = match self.pattern {
+            None => Some(Pattern::Grid),
+            Some(Pattern::Grid) => Some(Pattern::Dots),
+            Some(Pattern::Dots) => Some(Pattern::Lines),
+            Some(Pattern::Lines) => None,
+        };
+    }
+
+    pub fn next_spacing(&mut self) {
+        let next = Self::SPACINGS
+            .iter()
+            .position(|spacing| *spacing > self.spacing)
+            .unwrap_or(0);
+
+        self.spacing = Self::SPACINGS[next];
+    }
+
+    pub fn toggle_snap(&mut self) {
+        self.snap = !self.snap;
+    }
+
+    /// The nearest ruling intersection to `point`, if snapping is on and a
+    /// ruling is shown. Ruled lines have no intersections, so only the
+    /// height snaps to them.
+    pub fn snap(&self, point: Point) -> Point {
+        let round = |value: f32| (value / self.spacing).round() * self.spacing;
+
+        match self.pattern {
+            Some(_) if !self.snap => point,
+            Some(Pattern::Grid | Pattern::Dots) => Point::new(round(point.x), round(point.y)),
+            Some(Pattern::Lines) => Point::new(point.x, round(point.y)),
+            None => point,
+        }
+    }
+}
+
+impl Default for Paper {
+    fn default() -> Self {
+        Self {
+            pattern: None,
+            spacing: 24.0,
+            snap: true,
+        }
+    }
+}
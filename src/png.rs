@@ -0,0 +1,243 @@
+use crate::document::{Document, Label, Stroke, Tool};
+use crate::export;
+use crate::highlighter;
+use crate::smoothing::{self, Outline, Segment, Smoothing};
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache};
+use iced::Color;
+use tiny_skia::{FillRule, LineCap, LineJoin, Paint, PathBuilder, Pixmap, Rect, Transform};
+
+/// Rasterizes the strokes and labels of `document` on the CPU, framed like
+/// the SVG export and `scale` times their size on screen.
+///
+/// Labels are set in the system monospace font, as iced does on screen.
+pub fn render(
+    document: &Document,
+    smoothing: Smoothing,
+    background: Color,
+    scale: f32,
+) -> Option<Pixmap> {
+    let bounds = export::extent(document);
+    let width = (bounds.width * scale).ceil() as u32;
+    let height = (bounds.height * scale).ceil() as u32;
+
+    let mut pixmap = Pixmap::new(width, height)?;
+    pixmap.fill(color(background)?);
+
+    let transform = Transform::from_scale(scale, scale).pre_translate(-bounds.x, -bounds.y);
+
+    // Highlighters go beneath the pen, as on the canvas.
+    let (highlights, pens): (Vec<_>, Vec<_>) = document
+        .strokes()
+        .iter()
+        .partition(|stroke| stroke.tool == Tool::Highlighter);
+
+    for stroke in highlights {
+        draw_highlight(&mut pixmap, stroke, transform);
+    }
+
+    for stroke in pens {
+        draw_stroke(&mut pixmap, stroke, smoothing, transform);
+    }
+
+    if !document.labels().is_empty() {
+        let mut fonts = FontSystem::new();
+        let mut glyphs = SwashCache::new();
+
+        for label in document.labels() {
+            draw_label(&mut pixmap, &mut fonts, &mut glyphs, label, transform, scale);
+        }
+    }
+
+    Some(pixmap)
+}
+
+fn draw_highlight(pixmap: &mut Pixmap, stroke: &Stroke, transform: Transform) {
+    let mut builder = PathBuilder::new();
+
+    for polygon in highlighter::outline(&stroke.points, stroke.width) {
+        if let Some((first, rest)) = polygon.split_first() {
+            builder.move_to(first.x, first.y);
+
+            for point in rest {
+                builder.line_to(point.x, point.y);
+            }
+
+            builder.close();
+        }
+    }
+
+    if let (Some(path), Some(paint)) = (builder.finish(), paint(stroke.color)) {
+        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+    }
+}
+
+fn draw_stroke(pixmap: &mut Pixmap, stroke: &Stroke, smoothing: Smoothing, transform: Transform) {
+    let Some(paint) = paint(stroke.color) else {
+        return;
+    };
+
+    let (start, segments, arrow_head) = match smoothing::outline(stroke, smoothing) {
+        None => return,
+        Some(Outline::Dot { center, radius }) => {
+            if let Some(dot) = PathBuilder::from_circle(center.x, center.y, radius) {
+                pixmap.fill_path(&dot, &paint, FillRule::Winding, transform, None);
+            }
+            return;
+        }
+        Some(Outline::Path { start, segments, arrow_head }) => (start, segments, arrow_head),
+    };
+
+    let mut builder = PathBuilder::new();
+    builder.move_to(start.x, start.y);
+
+    for segment in segments {
+        match segment {
+            Segment::Line(to) => builder.line_to(to.x, to.y),
+            Segment::Cubic { control_a, control_b, to } => builder.cubic_to(
+                control_a.x,
+                control_a.y,
+                control_b.x,
+                control_b.y,
+                to.x,
+                to.y,
+            ),
+        }
+    }
+
+    if let Some([left, tip, right]) = arrow_head {
+        builder.move_to(left.x, left.y);
+        builder.line_to(tip.x, tip.y);
+        builder.line_to(right.x, right.y);
+    }
+
+    let Some(path) = builder.finish() else {
+        return;
+    };
+
+    let pen = tiny_skia::Stroke {
+        width: stroke.width,
+        line_cap: LineCap::Round,
+        line_join: LineJoin::Round,
+        ..tiny_skia::Stroke::default()
+    };
+
+    pixmap.stroke_path(&path, &paint, &pen, transform, None);
+}
+
+fn draw_label(
+    pixmap: &mut Pixmap,
+    fonts: &mut FontSystem,
+    glyphs: &mut SwashCache,
+    label: &Label,
+    transform: Transform,
+    scale: f32,
+) {
+    // Glyphs are laid out at their final size, so they stay sharp when scaled.
+    let size = label.size * scale;
+    let mut buffer = Buffer::new(fonts, Metrics::new(size, size * Label::LINE_HEIGHT));
+
+    buffer.set_size(fonts, f32::MAX, size * Label::LINE_HEIGHT);
+    let attrs = Attrs::new().family(Family::Monospace);
+    buffer.set_text(fonts, &label.content, attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(fonts);
+
+    let mut origin = tiny_skia::Point::from_xy(label.position.x, label.position.y);
+    transform.map_points(std::slice::from_mut(&mut origin));
+
+    let ink = cosmic_text::Color::rgba(0, 0, 0, 255);
+
+    buffer.draw(fonts, glyphs, ink, |x, y, width, height, coverage| {
+        let alpha = label.color.a * coverage.a() as f32 / 255.0;
+
+        let (Some(pixel), Some(paint)) = (
+            Rect::from_xywh(origin.x + x as f32, origin.y + y as f32, width as f32, height as f32),
+            paint(Color { a: alpha, ..label.color }),
+        ) else {
+            return;
+        };
+
+        pixmap.fill_rect(pixel, &paint, Transform::identity(), None);
+    });
+}
+
+fn color(color: Color) -> Option<tiny_skia::Color> {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+}
+
+fn paint(ink: Color) -> Option<Paint<'static>> {
+    let mut paint = Paint::default();
+    paint.set_color(color(ink)?);
+
+    Some(paint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::Point;
+
+    const RED: Color = Color::from_rgb(1.0, 0.0, 0.0);
+
+    /// A red pen line four pixels wide from (0, 0) to (40, 0), which with the
+    /// margin spans (-18, -18) to (58, 18).
+    fn line() -> Document {
+        let mut stroke = Stroke::new(Point::ORIGIN, RED, 4.0, Tool::Pen);
+        stroke.push(Point::new(40.0, 0.0));
+
+        let mut document = Document::new();
+        document.restore_strokes(vec![stroke]);
+        document
+    }
+
+    fn rgba(pixmap: &Pixmap, x: u32, y: u32) -> [u8; 4] {
+        let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+
+        [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+    }
+
+    #[test]
+    fn stroke_at_screen_size() {
+        let pixmap = render(&line(), Smoothing::default(), Color::WHITE, 1.0).unwrap();
+
+        assert_eq!((pixmap.width(), pixmap.height()), (76, 36));
+        // On the line, halfway along it.
+        assert_eq!(rgba(&pixmap, 38, 18), [255, 0, 0, 255]);
+        // Just past its edge, and in the margin.
+        assert_eq!(rgba(&pixmap, 38, 21), [255, 255, 255, 255]);
+        assert_eq!(rgba(&pixmap, 2, 2), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn stroke_at_twice_the_size() {
+        let pixmap = render(&line(), Smoothing::default(), Color::WHITE, 2.0).unwrap();
+
+        assert_eq!((pixmap.width(), pixmap.height()), (152, 72));
+        assert_eq!(rgba(&pixmap, 76, 36), [255, 0, 0, 255]);
+        // The line is eight pixels wide now.
+        assert_eq!(rgba(&pixmap, 76, 39), [255, 0, 0, 255]);
+        assert_eq!(rgba(&pixmap, 76, 42), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn labels_are_drawn() {
+        // Labels are set in a system font, and build machines may have none.
+        if FontSystem::new().db().faces().next().is_none() {
+            eprintln!("no fonts installed, skipping");
+            return;
+        }
+
+        let mut label = Label::new(Point::ORIGIN, RED, 20.0);
+        label.content = "MW".to_string();
+
+        let mut document = Document::new();
+        document.insert_label(0, label);
+
+        let pixmap = render(&document, Smoothing::default(), Color::WHITE, 1.0).unwrap();
+        let inked = (0..pixmap.height())
+            .flat_map(|y| (0..pixmap.width()).map(move |x| (x, y)))
+            .filter(|(x, y)| rgba(&pixmap, *x, *y) != [255, 255, 255, 255])
+            .count();
+
+        assert!(inked > 20, "only {} pixels inked", inked);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::document::Tool;
+use crate::geometry::distance_to_segment;
+use crate::shape;
+use crate::simplify::simplify;
+use iced::Point;
+
+/// Strokes smaller than this are left alone.
+const MIN_SIZE: f32 = 20.0;
+/// How far, relative to its length, a line may bow before it isn't a line.
+const LINE_TOLERANCE: f32 = 0.06;
+/// How close, relative to its length, the ends must be for a closed shape.
+const CLOSED_TOLERANCE: f32 = 0.2;
+/// Mean radial deviation, relative to the radii, accepted for an ellipse.
+const ELLIPSE_TOLERANCE: f32 = 0.09;
+/// Corner detection tolerance, relative to the bounding box diagonal.
+const CORNER_TOLERANCE: f32 = 0.08;
+/// Vertices turning less than this (in radians) are not corners.
+const MIN_CORNER_ANGLE: f32 = 0.45;
+/// Edges within this angle (in radians) of an axis are treated as aligned.
+const AXIS_TOLERANCE: f32 = 0.26;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recognized {
+    pub tool: Tool,
+    pub points: Vec<Point>,
+}
+
+/// Recognizes a freehand stroke as a line, arrow, triangle, rectangle or
+/// ellipse, returning the clean shape to replace it with.
+pub fn recognize(points: &[Point]) -> Option<Recognized> {
+    let (min, max) = bounds(points)?;
+    let diagonal = min.distance(max);
+    let length = path_length(points);
+
+    if diagonal < MIN_SIZE {
+        return None;
+    }
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    if first.distance(last) > CLOSED_TOLERANCE * length {
+        return recognize_open(points, length);
+    }
+
+    if is_ellipse(points, min, max) {
+        return Some(Recognized {
+            tool: Tool::Ellipse,
+            points: shape::outline(Tool::Ellipse, min, max, false),
+        });
+    }
+
+    let corners = corners(points, CORNER_TOLERANCE * diagonal);
+
+    match corners.len() {
+        3 => {
+            let mut points = corners;
+            points.push(points[0]);
+
+            Some(Recognized {
+                tool: Tool::Triangle,
+                points,
+            })
+        }
+        4 => {
+            let points = if is_axis_aligned(&corners) {
+                shape::outline(Tool::Rectangle, min, max, false)
+            } else {
+                let mut points = corners;
+                points.push(points[0]);
+                points
+            };
+
+            Some(Recognized {
+                tool: Tool::Rectangle,
+                points,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn recognize_open(points: &[Point], length: f32) -> Option<Recognized> {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let chord = first.distance(last);
+
+    let bow = points
+        .iter()
+        .map(|point| distance_to_segment(*point, first, last))
+        .fold(0.0, f32::max);
+
+    if bow <= LINE_TOLERANCE * chord {
+        return Some(Recognized {
+            tool: Tool::Line,
+            points: vec![first, last],
+        });
+    }
+
+    // A one-stroke arrow is a long shaft followed by a short scribble for the
+    // head that stays around the tip.
+    let simplified = simplify(points, LINE_TOLERANCE * length);
+
+    if let [tail, tip, head @ ..] = simplified.as_slice() {
+        let shaft = tail.distance(*tip);
+        let head_size = 0.45 * shaft;
+
+        if !head.is_empty()
+            && shaft >= 0.5 * length
+            && head.iter().all(|point| point.distance(*tip) <= head_size)
+        {
+            return Some(Recognized {
+                tool: Tool::Arrow,
+                points: vec![*tail, *tip],
+            });
+        }
+    }
+
+    None
+}
+
+fn is_ellipse(points: &[Point], min: Point, max: Point) -> bool {
+    let center = Point::new((min.x + max.x) / 2.0, (min.y + max.y) / 2.0);
+    let radius_x = (max.x - min.x) / 2.0;
+    let radius_y = (max.y - min.y) / 2.0;
+
+    if radius_x <= 0.0 || radius_y <= 0.0 {
+        return false;
+    }
+
+    let deviation = points
+        .iter()
+        .map(|point| {
+            let x = (point.x - center.x) / radius_x;
+            let y = (point.y - center.y) / radius_y;
+
+            ((x * x + y * y).sqrt() - 1.0).abs()
+        })
+        .sum::<f32>()
+        / points.len() as f32;
+
+    deviation <= ELLIPSE_TOLERANCE
+}
+
+/// The corners of a closed stroke, without repeating the first one.
+fn corners(points: &[Point], tolerance: f32) -> Vec<Point> {
+    let mut corners = simplify(points, tolerance);
+
+    // The closing point duplicates the starting one.
+    corners.pop();
+
+    // The stroke may start halfway along an edge, and the hand may wobble;
+    // drop vertices that barely turn until only real corners are left.
+    while corners.len() > 2 {
+        let count = corners.len();
+        let flattest = (0..count)
+            .map(|i| {
+                let previous = corners[(i + count - 1) % count];
+                let next = corners[(i + 1) % count];
+
+                (i, turn(previous, corners[i], next))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match flattest {
+            Some((index, angle)) if angle < MIN_CORNER_ANGLE => {
+                corners.remove(index);
+            }
+            _ => break,
+        }
+    }
+
+    corners
+}
+
+/// How much the direction changes at `point`, in radians.
+fn turn(previous: Point, point: Point, next: Point) -> f32 {
+    let incoming = point - previous;
+    let outgoing = next - point;
+    let angle = outgoing.y.atan2(outgoing.x) - incoming.y.atan2(incoming.x);
+
+    angle.sin().atan2(angle.cos()).abs()
+}
+
+fn is_axis_aligned(corners: &[Point]) -> bool {
+    (0..corners.len()).all(|i| {
+        let edge = corners[(i + 1) % corners.len()] - corners[i];
+        let angle = edge.y.atan2(edge.x).abs() % std::f32::consts::FRAC_PI_2;
+
+        angle.min(std::f32::consts::FRAC_PI_2 - angle) <= AXIS_TOLERANCE
+    })
+}
+
+fn bounds(points: &[Point]) -> Option<(Point, Point)> {
+    let first = points.first()?;
+
+    Some(points.iter().fold((*first, *first), |(min, max), point| {
+        (
+            Point::new(min.x.min(point.x), min.y.min(point.y)),
+            Point::new(max.x.max(point.x), max.y.max(point.y)),
+        )
+    }))
+}
+
+fn path_length(points: &[Point]) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    /// Samples a hand-drawn path through `vertices`, with a small
+    /// deterministic wobble standing in for hand tremor.
+    fn trace(vertices: &[Point], samples_per_edge: usize) -> Vec<Point> {
+        let mut points = Vec::new();
+
+        for (edge, pair) in vertices.windows(2).enumerate() {
+            for i in 0..samples_per_edge {
+                let t = i as f32 / samples_per_edge as f32;
+                let wobble = ((edge * samples_per_edge + i) as f32 * 1.7).sin() * 1.5;
+
+                points.push(Point::new(
+                    pair[0].x + (pair[1].x - pair[0].x) * t + wobble,
+                    pair[0].y + (pair[1].y - pair[0].y) * t - wobble,
+                ));
+            }
+        }
+
+        points.extend(vertices.last());
+        points
+    }
+
+    fn ellipse(center: Point, radius_x: f32, radius_y: f32, turns: f32) -> Vec<Point> {
+        (0..=80)
+            .map(|i| {
+                let angle = i as f32 / 80.0 * TAU * turns;
+                let wobble = (i as f32 * 2.3).sin() * 2.0;
+
+                Point::new(
+                    center.x + (radius_x + wobble) * angle.cos(),
+                    center.y + (radius_y + wobble) * angle.sin(),
+                )
+            })
+            .collect()
+    }
+
+    /// The distinct vertices of a recognized shape, leaving out the point
+    /// that closes it.
+    fn corner_count(shape: &Recognized) -> usize {
+        match shape.points.as_slice() {
+            [first, .., last] if first.distance(*last) < 0.01 => shape.points.len() - 1,
+            points => points.len(),
+        }
+    }
+
+    fn assert_recognized(points: &[Point], tool: Tool, corners: usize) {
+        let shape = recognize(points).unwrap_or_else(|| panic!("no {:?} recognized", tool));
+
+        assert_eq!(shape.tool, tool);
+        assert_eq!(corner_count(&shape), corners);
+    }
+
+    #[test]
+    fn line() {
+        let points = trace(&[Point::new(10.0, 10.0), Point::new(200.0, 60.0)], 40);
+
+        assert_recognized(&points, Tool::Line, 2);
+    }
+
+    #[test]
+    fn arrow() {
+        let points = trace(
+            &[
+                Point::new(10.0, 100.0),
+                Point::new(200.0, 100.0),
+                Point::new(180.0, 85.0),
+                Point::new(200.0, 100.0),
+                Point::new(180.0, 115.0),
+            ],
+            20,
+        );
+
+        assert_recognized(&points, Tool::Arrow, 2);
+    }
+
+    #[test]
+    fn triangle() {
+        let apex = Point::new(100.0, 10.0);
+        let points = trace(&[apex, Point::new(180.0, 150.0), Point::new(20.0, 150.0), apex], 30);
+
+        assert_recognized(&points, Tool::Triangle, 3);
+    }
+
+    #[test]
+    fn rectangle() {
+        let start = Point::new(60.0, 20.0);
+        let points = trace(
+            &[
+                start,
+                Point::new(220.0, 20.0),
+                Point::new(220.0, 120.0),
+                Point::new(20.0, 120.0),
+                Point::new(20.0, 20.0),
+                start,
+            ],
+            30,
+        );
+
+        assert_recognized(&points, Tool::Rectangle, 4);
+    }
+
+    #[test]
+    fn rotated_rectangle() {
+        let vertices = [
+            Point::new(100.0, 20.0),
+            Point::new(220.0, 100.0),
+            Point::new(160.0, 190.0),
+            Point::new(40.0, 110.0),
+            Point::new(100.0, 20.0),
+        ];
+        let points = trace(&vertices, 30);
+        let shape = recognize(&points).expect("no rectangle recognized");
+
+        assert_eq!(shape.tool, Tool::Rectangle);
+        assert_eq!(corner_count(&shape), 4);
+        // The corners follow the stroke rather than its bounding box.
+        assert!(shape
+            .points
+            .iter()
+            .all(|corner| vertices.iter().any(|vertex| corner.distance(*vertex) < 10.0)));
+    }
+
+    #[test]
+    fn ellipse_outline() {
+        let points = ellipse(Point::new(150.0, 100.0), 120.0, 60.0, 1.0);
+        let shape = recognize(&points).expect("no ellipse recognized");
+
+        assert_eq!(shape.tool, Tool::Ellipse);
+        assert_eq!(corner_count(&shape), shape::ELLIPSE_SEGMENTS);
+    }
+
+    #[test]
+    fn scribble_is_not_recognized() {
+        let points: Vec<Point> = (0..120)
+            .map(|i| {
+                let t = i as f32 * 0.9;
+                Point::new(100.0 + t.sin() * 60.0 + i as f32, 100.0 + (t * 2.3).cos() * 50.0)
+            })
+            .collect();
+
+        assert_eq!(recognize(&points), None);
+    }
+
+    #[test]
+    fn tiny_stroke_is_not_recognized() {
+        let points = trace(&[Point::new(10.0, 10.0), Point::new(10.0 + MIN_SIZE / 2.0, 12.0)], 10);
+
+        assert_eq!(recognize(&points), None);
+    }
+
+    #[test]
+    fn open_curve_is_not_recognized() {
+        let points = ellipse(Point::new(150.0, 100.0), 100.0, 100.0, 0.5);
+
+        assert_eq!(recognize(&points), None);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::document::{Document, Stroke};
+use crate::geometry::{self, distance_to_segment, Affine};
+use iced::{Point, Rectangle, Size};
+
+/// Half the side of the square scale handles.
+pub const HANDLE_SIZE: f32 = 5.0;
+/// How far above the bounding box the rotation handle sits.
+pub const ROTATE_OFFSET: f32 = 24.0;
+/// How close to a stroke a click must land to pick it.
+const PICK_TOLERANCE: f32 = 4.0;
+/// A lasso that never leaves this radius is treated as a click.
+const CLICK_RADIUS: f32 = 4.0;
+/// Keeps a selection from being scaled down to nothing.
+const MIN_SCALE: f32 = 0.05;
+
+/// The part of a selection being dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handle {
+    Body,
+    Corner,
+    Rotate,
+}
+
+/// The selected strokes, by index in ascending order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    indices: Vec<usize>,
+}
+
+impl Selection {
+    pub fn new(indices: Vec<usize>) -> Self {
+        Self { indices }
+    }
+
+    pub fn single(index: usize) -> Self {
+        Self::new(vec![index])
+    }
+
+    /// Selects the strokes lying entirely inside `lasso`, or the topmost
+    /// stroke under it if the lasso was just a click.
+    pub fn lasso(document: &Document, lasso: &[Point]) -> Option<Self> {
+        let start = *lasso.first()?;
+
+        let is_click = lasso.iter().all(|point| point.distance(start) <= CLICK_RADIUS);
+
+        let indices: Vec<usize> = if is_click {
+            pick(document, start).into_iter().collect()
+        } else {
+            document
+                .strokes()
+                .iter()
+                .enumerate()
+                .filter(|(_, stroke)| {
+                    stroke.points.iter().all(|point| geometry::contains(lasso, *point))
+                })
+                .map(|(index, _)| index)
+                .collect()
+        };
+
+        (!indices.is_empty()).then_some(Self { indices })
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// The box around the selected ink.
+    pub fn bounds(&self, document: &Document) -> Option<Rectangle> {
+        bounds(self.indices.iter().filter_map(|index| document.strokes().get(*index)))
+    }
+
+    pub fn handle_at(&self, document: &Document, position: Point) -> Option<Handle> {
+        let bounds = self.bounds(document)?;
+        let reach = HANDLE_SIZE + 2.0;
+
+        if rotate_handle(bounds).distance(position) <= reach {
+            Some(Handle::Rotate)
+        } else if corners(bounds).iter().any(|corner| corner.distance(position) <= reach) {
+            Some(Handle::Corner)
+        } else if bounds.contains(position) {
+            Some(Handle::Body)
+        } else {
+            None
+        }
+    }
+}
+
+/// The box around `strokes`, including their width.
+pub fn bounds<'a>(strokes: impl IntoIterator<Item = &'a Stroke>) -> Option<Rectangle> {
+    let mut extent: Option<(Point, Point)> = None;
+
+    for stroke in strokes {
+        let reach = stroke.width / 2.0;
+
+        for point in &stroke.points {
+            let (min, max) = extent.get_or_insert((*point, *point));
+            min.x = min.x.min(point.x - reach);
+            min.y = min.y.min(point.y - reach);
+            max.x = max.x.max(point.x + reach);
+            max.y = max.y.max(point.y + reach);
+        }
+    }
+
+    extent.map(|(min, max)| Rectangle::new(min, Size::new(max.x - min.x, max.y - min.y)))
+}
+
+pub fn corners(bounds: Rectangle) -> [Point; 4] {
+    [
+        Point::new(bounds.x, bounds.y),
+        Point::new(bounds.x + bounds.width, bounds.y),
+        Point::new(bounds.x + bounds.width, bounds.y + bounds.height),
+        Point::new(bounds.x, bounds.y + bounds.height),
+    ]
+}
+
+pub fn rotate_handle(bounds: Rectangle) -> Point {
+    Point::new(bounds.center_x(), bounds.y - ROTATE_OFFSET)
+}
+
+/// The topmost stroke within reach of `position`.
+pub fn pick(document: &Document, position: Point) -> Option<usize> {
+    document
+        .strokes()
+        .iter()
+        .rposition(|stroke| touches(stroke, position))
+}
+
+/// Whether `position` is on `stroke` or close enough to pick it.
+pub fn touches(stroke: &Stroke, position: Point) -> bool {
+    let reach = stroke.width / 2.0 + PICK_TOLERANCE;
+
+    match stroke.points.as_slice() {
+        [] => false,
+        [point] => point.distance(position) <= reach,
+        points => points
+            .windows(2)
+            .any(|pair| distance_to_segment(position, pair[0], pair[1]) <= reach),
+    }
+}
+
+/// The transform for dragging `handle` from `start` to `position`; corners
+/// scale and the rotation handle rotates around `center`.
+pub fn drag(handle: Handle, center: Point, start: Point, position: Point) -> Affine {
+    match handle {
+        Handle::Body => Affine::translate(position - start),
+        Handle::Corner => {
+            let from = start.distance(center);
+
+            if from == 0.0 {
+                return Affine::scale(center, 1.0);
+            }
+
+            Affine::scale(center, (position.distance(center) / from).max(MIN_SCALE))
+        }
+        Handle::Rotate => {
+            let angle = |point: Point| (point.y - center.y).atan2(point.x - center.x);
+
+            Affine::rotate(center, angle(position) - angle(start))
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::board::{Board, Sheet};
+use crate::document::{Document, Label, Stroke, Tool};
+use crate::history::History;
+use iced::{Color, Point};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where sessions are saved and loaded from, in the working directory.
+pub const FILE: &str = "vivopaint-session.json";
+
+/// The current format version. Older files are upgraded by [`upgrade`];
+/// bump this and add a step there whenever the format changes.
+///
+/// - 0: no header, only the `boards`; files without a `version` are read as this.
+/// - 1: adds `version`, `saved_at` and the `board` shown when saving.
+const VERSION: u64 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    /// The file was written by a newer or unknown version.
+    Version(u64),
+    /// A stroke or label in the file is not valid.
+    Invalid,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Format(error) => write!(f, "{}", error),
+            Error::Version(version) => write!(f, "unsupported session version {}", version),
+            Error::Invalid => write!(f, "invalid stroke or label"),
+        }
+    }
+}
+
+/// The ink of every board, as restored from a session file.
+#[derive(Debug)]
+pub struct Session {
+    pub board: Board,
+    pub sheets: HashMap<Board, Sheet>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct File {
+    version: u64,
+    /// Seconds since the Unix epoch.
+    saved_at: u64,
+    board: String,
+    boards: Vec<BoardData>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BoardData {
+    board: String,
+    strokes: Vec<StrokeData>,
+    labels: Vec<LabelData>,
+}
+
+/// A stroke as written to session files and copied to the clipboard.
+#[derive(Serialize, Deserialize)]
+pub struct StrokeData {
+    tool: String,
+    color: [f32; 4],
+    width: f32,
+    points: Vec<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw_points: Option<Vec<[f32; 2]>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LabelData {
+    position: [f32; 2],
+    content: String,
+    color: [f32; 4],
+    size: f32,
+}
+
+/// Writes `board`, shown with `document`, and the other boards in `sheets`.
+pub fn save(
+    path: &Path,
+    board: Board,
+    document: &Document,
+    sheets: &HashMap<Board, Sheet>,
+) -> Result<(), Error> {
+    let documents = std::iter::once((board, document))
+        .chain(sheets.iter().map(|(board, sheet)| (*board, &sheet.document)));
+
+    let boards = documents
+        .filter(|(_, document)| !document.strokes().is_empty() || !document.labels().is_empty())
+        .map(|(board, document)| BoardData {
+            board: board.name().to_string(),
+            strokes: document.strokes().iter().map(stroke_data).collect(),
+            labels: document.labels().iter().map(label_data).collect(),
+        })
+        .collect();
+
+    let file = File {
+        version: VERSION,
+        saved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+        board: board.name().to_string(),
+        boards,
+    };
+
+    let json = serde_json::to_string_pretty(&file).map_err(Error::Format)?;
+
+    std::fs::write(path, json).map_err(Error::Io)
+}
+
+pub fn load(path: &Path) -> Result<Session, Error> {
+    let json = std::fs::read_to_string(path).map_err(Error::Io)?;
+    let value: Value = serde_json::from_str(&json).map_err(Error::Format)?;
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    let file: File = serde_json::from_value(upgrade(value, version)?).map_err(Error::Format)?;
+
+    let mut sheets = HashMap::new();
+
+    for data in file.boards {
+        let board = Board::from_name(&data.board).ok_or(Error::Invalid)?;
+        let mut document = Document::new();
+
+        let strokes = data.strokes.iter().map(stroke).collect::<Option<_>>();
+
+        document.restore_strokes(strokes.ok_or(Error::Invalid)?);
+        document.restore_labels(data.labels.iter().map(label).collect());
+        sheets.insert(board, Sheet { document, history: History::new() });
+    }
+
+    Ok(Session {
+        board: Board::from_name(&file.board).unwrap_or(Board::Overlay),
+        sheets,
+    })
+}
+
+/// Brings a session written by format `version` up to the current one, one
+/// version at a time.
+fn upgrade(mut session: Value, version: u64) -> Result<Value, Error> {
+    match version {
+        VERSION => Ok(session),
+        0 => {
+            let Value::Object(fields) = &mut session else {
+                return Err(Error::Invalid);
+            };
+
+            fields.insert("version".to_string(), Value::from(1));
+            fields.entry("saved_at").or_insert(Value::from(0));
+            fields.entry("board").or_insert(Value::from(Board::Overlay.name()));
+
+            upgrade(session, 1)
+        }
+        _ => Err(Error::Version(version)),
+    }
+}
+
+pub fn stroke_data(stroke: &Stroke) -> StrokeData {
+    StrokeData {
+        tool: stroke.tool.name().to_string(),
+        color: rgba(stroke.color),
+        width: stroke.width,
+        points: stroke.points.iter().map(|point| [point.x, point.y]).collect(),
+        raw_points: stroke
+            .raw_points
+            .as_ref()
+            .map(|points| points.iter().map(|point| [point.x, point.y]).collect()),
+    }
+}
+
+fn label_data(label: &Label) -> LabelData {
+    LabelData {
+        position: [label.position.x, label.position.y],
+        content: label.content.clone(),
+        color: rgba(label.color),
+        size: label.size,
+    }
+}
+
+/// Reads a stroke back as a new finished stroke, or `None` if it is invalid.
+pub fn stroke(data: &StrokeData) -> Option<Stroke> {
+    let tool = Tool::from_name(&data.tool)?;
+    let points: Vec<Point> = data.points.iter().map(|[x, y]| Point::new(*x, *y)).collect();
+
+    let mut stroke = Stroke::new(*points.first()?, color(data.color), data.width, tool);
+    stroke.points = points;
+    stroke.raw_points = data
+        .raw_points
+        .as_ref()
+        .map(|points| points.iter().map(|[x, y]| Point::new(*x, *y)).collect());
+    stroke.finish();
+
+    Some(stroke)
+}
+
+fn label(data: &LabelData) -> Label {
+    let [x, y] = data.position;
+    let mut label = Label::new(Point::new(x, y), color(data.color), data.size);
+    label.content = data.content.clone();
+
+    label
+}
+
+fn rgba(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+fn color([r, g, b, a]: [f32; 4]) -> Color {
+    Color::from_rgba(r, g, b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A file in the temporary directory, unique to the test calling it.
+    fn scratch(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vivopaint-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = scratch("round-trip");
+
+        let red = Color::from_rgb(1.0, 0.0, 0.0);
+        let mut stroke = Stroke::new(Point::new(1.0, 2.0), red, 4.0, Tool::Arrow);
+        stroke.push(Point::new(30.0, 40.0));
+        stroke.raw_points = Some(vec![
+            Point::new(1.0, 2.0),
+            Point::new(15.0, 20.5),
+            Point::new(30.0, 40.0),
+        ]);
+
+        let mut label = Label::new(Point::new(5.0, 6.0), Color::BLACK, 24.0);
+        label.content = "Hi & bye".to_string();
+
+        let mut document = Document::new();
+        document.restore_strokes(vec![stroke.clone()]);
+        document.insert_label(0, label.clone());
+
+        let mut whiteboard = Document::new();
+        whiteboard.restore_strokes(vec![Stroke::new(
+            Point::new(9.0, 9.0),
+            Color::WHITE,
+            2.0,
+            Tool::Pen,
+        )]);
+
+        let mut sheets = HashMap::new();
+        sheets.insert(Board::Whiteboard, Sheet { document: whiteboard, history: History::new() });
+        sheets.insert(Board::Blackboard, Sheet::default());
+
+        save(&path, Board::Overlay, &document, &sheets).unwrap();
+        let session = load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(session.board, Board::Overlay);
+        // Empty boards are not written.
+        assert_eq!(session.sheets.len(), 2);
+
+        let overlay = &session.sheets[&Board::Overlay].document;
+        let loaded = &overlay.strokes()[0];
+
+        assert_eq!(overlay.strokes().len(), 1);
+        assert_eq!(loaded.tool, stroke.tool);
+        assert_eq!(loaded.color, stroke.color);
+        assert_eq!(loaded.width, stroke.width);
+        assert_eq!(loaded.points, stroke.points);
+        assert_eq!(loaded.raw_points, stroke.raw_points);
+        assert_eq!(overlay.labels()[0].content, label.content);
+        assert_eq!(overlay.labels()[0].position, label.position);
+        assert_eq!(session.sheets[&Board::Whiteboard].document.strokes().len(), 1);
+    }
+
+    #[test]
+    fn unversioned_file_is_upgraded() {
+        let path = scratch("unversioned");
+
+        let json = r#"{
+            "boards": [{
+                "board": "whiteboard",
+                "strokes": [{"tool": "pen", "color": [0, 0, 1, 1], "width": 3,
+                             "points": [[1, 2], [3, 4]]}],
+                "labels": []
+            }]
+        }"#;
+        std::fs::write(&path, json).unwrap();
+        let session = load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        let session = session.unwrap();
+        let strokes = session.sheets[&Board::Whiteboard].document.strokes();
+
+        assert_eq!(session.board, Board::Overlay);
+        assert_eq!(strokes.len(), 1);
+        assert_eq!(strokes[0].points, [Point::new(1.0, 2.0), Point::new(3.0, 4.0)]);
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let path = scratch("unknown-version");
+
+        let json = r#"{"version": 99, "saved_at": 0, "board": "overlay", "boards": []}"#;
+        std::fs::write(&path, json).unwrap();
+        let result = load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(result, Err(Error::Version(99))));
+    }
+}
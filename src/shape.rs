@@ -0,0 +1,91 @@
+use crate::document::Tool;
+use iced::{Point, Vector};
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+pub const ELLIPSE_SEGMENTS: usize = 64;
+const ARROW_HEAD_ANGLE: f32 = 0.5;
+
+/// The points of a shape dragged from `anchor` to `cursor`.
+///
+/// With `constrain`, lines snap to multiples of 45° and rectangles and
+/// ellipses become squares and circles.
+pub fn outline(tool: Tool, anchor: Point, cursor: Point, constrain: bool) -> Vec<Point> {
+    match tool {
+        Tool::Line | Tool::Arrow => {
+            let end = if constrain { snap_angle(anchor, cursor) } else { cursor };
+
+            vec![anchor, end]
+        }
+        Tool::Rectangle => {
+            let corner = if constrain { square(anchor, cursor) } else { cursor };
+
+            vec![
+                anchor,
+                Point::new(corner.x, anchor.y),
+                corner,
+                Point::new(anchor.x, corner.y),
+                anchor,
+            ]
+        }
+        Tool::Ellipse => {
+            let corner = if constrain { square(anchor, cursor) } else { cursor };
+            let center = Point::new((anchor.x + corner.x) / 2.0, (anchor.y + corner.y) / 2.0);
+            let radii = Vector::new((corner.x - anchor.x).abs() / 2.0, (corner.y - anchor.y).abs() / 2.0);
+
+            (0..=ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 * TAU / ELLIPSE_SEGMENTS as f32;
+                    Point::new(center.x + radii.x * angle.cos(), center.y + radii.y * angle.sin())
+                })
+                .collect()
+        }
+        Tool::Triangle => {
+            let corner = if constrain { square(anchor, cursor) } else { cursor };
+            let apex = Point::new((anchor.x + corner.x) / 2.0, anchor.y);
+
+            vec![apex, corner, Point::new(anchor.x, corner.y), apex]
+        }
+        Tool::Pen | Tool::Highlighter => vec![anchor, cursor],
+    }
+}
+
+/// The two barb ends of an arrow head pointing at `tip`.
+pub fn arrow_head(from: Point, tip: Point, width: f32) -> Option<[Point; 2]> {
+    let length = from.distance(tip);
+
+    if length == 0.0 {
+        return None;
+    }
+
+    let size = (width * 3.0).max(12.0).min(length);
+    let back = (from - tip) * (size / length);
+
+    Some([
+        tip + rotate(back, ARROW_HEAD_ANGLE),
+        tip + rotate(back, -ARROW_HEAD_ANGLE),
+    ])
+}
+
+fn snap_angle(anchor: Point, cursor: Point) -> Point {
+    let offset = cursor - anchor;
+    let length = anchor.distance(cursor);
+    let angle = (offset.y.atan2(offset.x) / FRAC_PI_4).round() * FRAC_PI_4;
+
+    Point::new(anchor.x + length * angle.cos(), anchor.y + length * angle.sin())
+}
+
+fn square(anchor: Point, cursor: Point) -> Point {
+    let offset = cursor - anchor;
+    let side = offset.x.abs().max(offset.y.abs());
+
+    Point::new(
+        anchor.x + side.copysign(offset.x),
+        anchor.y + side.copysign(offset.y),
+    )
+}
+
+fn rotate(vector: Vector, angle: f32) -> Vector {
+    let (sin, cos) = angle.sin_cos();
+
+    Vector::new(vector.x * cos - vector.y * sin, vector.x * sin + vector.y * cos)
+}
@@ -0,0 +1,52 @@
+use crate::geometry::distance_to_segment;
+use iced::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simplify {
+    /// Maximum distance, in screen pixels, a dropped point may lie from the simplified line.
+    pub tolerance: f32,
+    /// Keeps the original samples on the stroke, e.g. for replaying it.
+    pub keep_raw: bool,
+}
+
+impl Default for Simplify {
+    fn default() -> Self {
+        Self {
+            tolerance: 1.0,
+            keep_raw: false,
+        }
+    }
+}
+
+/// Ramer–Douglas–Peucker simplification of a polyline.
+pub fn simplify(points: &[Point], tolerance: f32) -> Vec<Point> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, distance_to_segment(points[i], points[first], points[last])))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
@@ -0,0 +1,120 @@
+use crate::document::{Stroke, Tool};
+use crate::shape;
+use iced::Point;
+
+/// A piece of a stroke outline, starting where the previous one ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Line(Point),
+    Cubic {
+        control_a: Point,
+        control_b: Point,
+        to: Point,
+    },
+}
+
+/// How a pen or shape stroke is drawn, whichever backend draws it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outline {
+    /// A single sample, filled as a circle.
+    Dot { center: Point, radius: f32 },
+    /// A path from `start` through `segments`, plus the barbs of an arrow
+    /// head as a polyline through the tip.
+    Path {
+        start: Point,
+        segments: Vec<Segment>,
+        arrow_head: Option<[Point; 3]>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Smoothing {
+    pub enabled: bool,
+    /// Tension of the Catmull-Rom spline, from `0.0` (straight lines) to `1.0`.
+    pub strength: f32,
+}
+
+impl Smoothing {
+    pub const STRENGTHS: [f32; 3] = [0.25, 0.5, 1.0];
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn next_strength(&mut self) {
+        let next = Self::STRENGTHS
+            .iter()
+            .position(|strength| *strength > self.strength)
+            .unwrap_or(0);
+
+        self.strength = Self::STRENGTHS[next];
+        self.enabled = true;
+    }
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strength: 0.5,
+        }
+    }
+}
+
+/// The outline of `stroke`, stroked with its width. Shapes keep their
+/// corners, so they are never smoothed.
+pub fn outline(stroke: &Stroke, smoothing: Smoothing) -> Option<Outline> {
+    let start = match stroke.points.as_slice() {
+        [] => return None,
+        [point] => {
+            return Some(Outline::Dot {
+                center: *point,
+                radius: stroke.width / 2.0,
+            })
+        }
+        [first, ..] => *first,
+    };
+
+    let smoothing = Smoothing {
+        enabled: smoothing.enabled && !stroke.tool.is_shape(),
+        ..smoothing
+    };
+
+    let arrow_head = match (stroke.tool, stroke.points.as_slice()) {
+        (Tool::Arrow, [.., from, tip]) => shape::arrow_head(*from, *tip, stroke.width)
+            .map(|[left, right]| [left, *tip, right]),
+        _ => None,
+    };
+
+    Some(Outline::Path {
+        start,
+        segments: segments(&stroke.points, smoothing),
+        arrow_head,
+    })
+}
+
+/// Turns the samples after `points[0]` into segments, fitting a Catmull-Rom
+/// spline through them as cubic Bezier curves when smoothing is enabled.
+pub fn segments(points: &[Point], smoothing: Smoothing) -> Vec<Segment> {
+    if !smoothing.enabled || smoothing.strength <= 0.0 || points.len() < 3 {
+        return points.iter().skip(1).copied().map(Segment::Line).collect();
+    }
+
+    let scale = smoothing.strength.min(1.0) / 6.0;
+    let last = points.len() - 1;
+
+    (0..last)
+        .map(|i| {
+            let p0 = points[i.saturating_sub(1)];
+            let p1 = points[i];
+            let p2 = points[i + 1];
+            let p3 = points[(i + 2).min(last)];
+
+            Segment::Cubic {
+                control_a: p1 + (p2 - p0) * scale,
+                control_b: p2 - (p3 - p1) * scale,
+                to: p2,
+            }
+        })
+        .collect()
+}
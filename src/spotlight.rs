@@ -0,0 +1,65 @@
+use iced::{Color, Point, Size};
+
+pub const DIM: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.6,
+};
+
+const MIN_RADIUS: f32 = 30.0;
+const MAX_RADIUS: f32 = 600.0;
+const SCROLL_STEP: f32 = 15.0;
+/// Rectangular holes are wider than they are tall, like most screen content.
+const RECTANGLE_ASPECT: f32 = 1.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hole {
+    Circle,
+    Rectangle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spotlight {
+    pub enabled: bool,
+    pub hole: Hole,
+    pub radius: f32,
+}
+
+impl Spotlight {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn toggle_hole(&mut self) {
+        self.hole = match self.hole {
+            Hole::Circle => Hole::Rectangle,
+            Hole::Rectangle => Hole::Circle,
+        };
+        self.enabled = true;
+    }
+
+    pub fn scroll(&mut self, lines: f32) {
+        self.radius = (self.radius + lines * SCROLL_STEP).clamp(MIN_RADIUS, MAX_RADIUS);
+    }
+
+    /// The top left corner and size of a rectangular hole around `center`.
+    pub fn rectangle(&self, center: Point) -> (Point, Size) {
+        let half_width = self.radius * RECTANGLE_ASPECT;
+
+        (
+            Point::new(center.x - half_width, center.y - self.radius),
+            Size::new(half_width * 2.0, self.radius * 2.0),
+        )
+    }
+}
+
+impl Default for Spotlight {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hole: Hole::Circle,
+            radius: 150.0,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use iced::Point;
+
+/// A "lazy brush": the pen is pulled along by the cursor on a string of
+/// length `radius`, so small movements inside that radius never reach the stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stabilizer {
+    pub enabled: bool,
+    pub radius: f32,
+    brush: Point,
+}
+
+impl Stabilizer {
+    pub const RADII: [f32; 3] = [10.0, 20.0, 40.0];
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn next_radius(&mut self) {
+        let next = Self::RADII
+            .iter()
+            .position(|radius| *radius > self.radius)
+            .unwrap_or(0);
+
+        self.radius = Self::RADII[next];
+        self.enabled = true;
+    }
+
+    pub fn brush(&self) -> Point {
+        self.brush
+    }
+
+    pub fn start(&mut self, position: Point) {
+        self.brush = position;
+    }
+
+    /// Moves the string's free end to `cursor` and returns the new brush
+    /// position, if the brush had to move at all.
+    pub fn follow(&mut self, cursor: Point) -> Option<Point> {
+        if !self.enabled {
+            self.brush = cursor;
+            return Some(cursor);
+        }
+
+        let distance = self.brush.distance(cursor);
+
+        if distance <= self.radius {
+            return None;
+        }
+
+        self.brush = self.brush + (cursor - self.brush) * ((distance - self.radius) / distance);
+
+        Some(self.brush)
+    }
+}
+
+impl Default for Stabilizer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 20.0,
+            brush: Point::ORIGIN,
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::document::{Document, Label, Stroke, Tool};
+use crate::export;
+use crate::highlighter;
+use crate::smoothing::{self, Outline, Segment, Smoothing};
+use iced::{Color, Point};
+use std::fmt::Write;
+
+/// Renders the strokes and labels of `document` as an SVG image framing the
+/// ink, styled the way the canvas draws them. `metadata`, if any, is kept in
+/// the image as text for VivoPaint to read back.
+pub fn export(
+    document: &Document,
+    smoothing: Smoothing,
+    background: Color,
+    metadata: Option<&str>,
+) -> String {
+    let bounds = export::extent(document);
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="{x} {y} {w} {h}">"#,
+        x = num(bounds.x),
+        y = num(bounds.y),
+        w = num(bounds.width),
+        h = num(bounds.height),
+    );
+
+    if let Some(metadata) = metadata {
+        let _ = writeln!(svg, "  <metadata>{}</metadata>", escape(metadata));
+    }
+
+    if background.a > 0.0 {
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            num(bounds.x),
+            num(bounds.y),
+            num(bounds.width),
+            num(bounds.height),
+            rgb(background),
+        );
+    }
+
+    // Highlighters go beneath the pen, as on the canvas.
+    let (highlights, pens): (Vec<_>, Vec<_>) = document
+        .strokes()
+        .iter()
+        .partition(|stroke| stroke.tool == Tool::Highlighter);
+
+    for stroke in highlights {
+        write_highlight(&mut svg, stroke);
+    }
+
+    for stroke in pens {
+        write_stroke(&mut svg, stroke, smoothing);
+    }
+
+    for label in document.labels() {
+        write_label(&mut svg, label);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_highlight(svg: &mut String, stroke: &Stroke) {
+    let mut data = String::new();
+
+    for polygon in highlighter::outline(&stroke.points, stroke.width) {
+        if let Some((first, rest)) = polygon.split_first() {
+            let _ = write!(data, "M{}", point(*first));
+
+            for vertex in rest {
+                let _ = write!(data, " L{}", point(*vertex));
+            }
+
+            data.push_str(" Z ");
+        }
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"  <path d="{}" fill="{}" fill-opacity="{}" fill-rule="nonzero"/>"#,
+        data.trim_end(),
+        rgb(stroke.color),
+        num(stroke.color.a),
+    );
+}
+
+fn write_stroke(svg: &mut String, stroke: &Stroke, smoothing: Smoothing) {
+    let (start, segments, arrow_head) = match smoothing::outline(stroke, smoothing) {
+        None => return,
+        Some(Outline::Dot { center, radius }) => {
+            let _ = writeln!(
+                svg,
+                r#"  <circle cx="{}" cy="{}" r="{}" fill="{}" fill-opacity="{}"/>"#,
+                num(center.x),
+                num(center.y),
+                num(radius),
+                rgb(stroke.color),
+                num(stroke.color.a),
+            );
+            return;
+        }
+        Some(Outline::Path { start, segments, arrow_head }) => (start, segments, arrow_head),
+    };
+
+    let mut data = format!("M{}", point(start));
+
+    for segment in segments {
+        let _ = match segment {
+            Segment::Line(to) => write!(data, " L{}", point(to)),
+            Segment::Cubic { control_a, control_b, to } => write!(
+                data,
+                " C{} {} {}",
+                point(control_a),
+                point(control_b),
+                point(to)
+            ),
+        };
+    }
+
+    if let Some([left, tip, right]) = arrow_head {
+        let _ = write!(data, " M{} L{} L{}", point(left), point(tip), point(right));
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"  <path d="{}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+        data,
+        rgb(stroke.color),
+        num(stroke.color.a),
+        num(stroke.width),
+    );
+}
+
+fn write_label(svg: &mut String, label: &Label) {
+    let _ = writeln!(
+        svg,
+        r#"  <text x="{}" y="{}" font-family="monospace" font-size="{}" dominant-baseline="text-before-edge" xml:space="preserve" fill="{}" fill-opacity="{}">{}</text>"#,
+        num(label.position.x),
+        num(label.position.y),
+        num(label.size),
+        rgb(label.color),
+        num(label.color.a),
+        escape(&label.content),
+    );
+}
+
+fn rgb(color: Color) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    format!("#{:02x}{:02x}{:02x}", channel(color.r), channel(color.g), channel(color.b))
+}
+
+fn point(point: Point) -> String {
+    format!("{},{}", num(point.x), num(point.y))
+}
+
+/// Formats a number with at most two decimals, dropping trailing zeros.
+fn num(value: f32) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Reverses [`escape`].
+pub fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape;
+
+    fn document(strokes: Vec<Stroke>) -> Document {
+        let mut document = Document::new();
+        document.restore_strokes(strokes);
+        document
+    }
+
+    fn stroke(tool: Tool, points: &[Point]) -> Stroke {
+        let mut stroke = Stroke::new(points[0], Color::from_rgb(1.0, 0.0, 0.0), 4.0, tool);
+        stroke.points = points.to_vec();
+        stroke
+    }
+
+    fn render(document: &Document) -> String {
+        export(document, Smoothing::default(), Color::TRANSPARENT, None)
+    }
+
+    #[test]
+    fn empty_document() {
+        let svg = render(&Document::new());
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"viewBox="-16 -16 32 32""#));
+        assert!(!svg.contains("<path"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn single_point_is_a_circle() {
+        let svg = render(&document(vec![stroke(Tool::Pen, &[Point::new(10.0, 20.0)])]));
+
+        assert!(svg.contains(r##"<circle cx="10" cy="20" r="2" fill="#ff0000""##));
+        assert!(!svg.contains("<path"));
+    }
+
+    #[test]
+    fn highlighter_fills_with_nonzero_rule() {
+        let points = [Point::new(0.0, 0.0), Point::new(50.0, 0.0), Point::new(50.0, 30.0)];
+        let svg = render(&document(vec![stroke(Tool::Highlighter, &points)]));
+
+        assert!(svg.contains(r#"fill-rule="nonzero""#));
+        assert!(!svg.contains(r#"fill="none""#));
+    }
+
+    #[test]
+    fn arrow_has_a_head() {
+        let from = Point::new(10.0, 10.0);
+        let tip = Point::new(110.0, 10.0);
+        let svg = render(&document(vec![stroke(Tool::Arrow, &[from, tip])]));
+
+        let [left, right] = shape::arrow_head(from, tip, 4.0).unwrap();
+        let head = format!(" M{} L{} L{}", point(left), point(tip), point(right));
+
+        assert!(svg.contains(&format!(r#"d="M10,10 L110,10{}""#, head)));
+    }
+
+    #[test]
+    fn label_text_is_escaped() {
+        let mut document = Document::new();
+        let mut label = Label::new(Point::new(0.0, 0.0), Color::BLACK, 20.0);
+        label.content = "a < b & c".to_string();
+        document.insert_label(0, label);
+
+        let svg = render(&document);
+
+        assert!(svg.contains(">a &lt; b &amp; c</text>"));
+    }
+
+    #[test]
+    fn view_box_includes_the_margin() {
+        let points = [Point::new(100.0, 50.0), Point::new(200.0, 150.0)];
+        let svg = render(&document(vec![stroke(Tool::Line, &points)]));
+
+        // The ink reaches half the width past its points, then the margin.
+        assert!(svg.contains(r#"width="136" height="136" viewBox="82 32 136 136""#));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::document::Label;
+
+/// Keys that edit the text under the caret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+/// A label being typed, either new or an existing one from the document.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    /// Index of the label in the document, if it is being edited.
+    pub index: Option<usize>,
+    pub label: Label,
+    /// Caret position, in characters.
+    pub caret: usize,
+}
+
+impl TextEdit {
+    pub fn new(label: Label) -> Self {
+        Self {
+            index: None,
+            label,
+            caret: 0,
+        }
+    }
+
+    pub fn edit(index: usize, label: Label) -> Self {
+        let caret = label.content.chars().count();
+
+        Self {
+            index: Some(index),
+            label,
+            caret,
+        }
+    }
+
+    pub fn insert(&mut self, character: char) {
+        let at = self.byte_offset(self.caret);
+        self.label.content.insert(at, character);
+        self.caret += 1;
+    }
+
+    pub fn apply(&mut self, key: Key) {
+        let length = self.label.content.chars().count();
+
+        match key {
+            Key::Backspace if self.caret > 0 => {
+                self.caret -= 1;
+                let at = self.byte_offset(self.caret);
+                self.label.content.remove(at);
+            }
+            Key::Delete if self.caret < length => {
+                let at = self.byte_offset(self.caret);
+                self.label.content.remove(at);
+            }
+            Key::Left => self.caret = self.caret.saturating_sub(1),
+            Key::Right => self.caret = (self.caret + 1).min(length),
+            Key::Home => self.caret = 0,
+            Key::End => self.caret = length,
+            _ => {}
+        }
+    }
+
+    fn byte_offset(&self, caret: usize) -> usize {
+        self.label
+            .content
+            .char_indices()
+            .nth(caret)
+            .map_or(self.label.content.len(), |(offset, _)| offset)
+    }
+}
@@ -0,0 +1,130 @@
+use iced::{keyboard, mouse, Point};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const RIPPLE: Duration = Duration::from_millis(600);
+pub const KEY_FADE: Duration = Duration::from_millis(2500);
+const MAX_KEYS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ripple {
+    pub position: Point,
+    pub button: mouse::Button,
+    pub at: Instant,
+}
+
+/// Shows mouse clicks and key presses, for screencasts.
+#[derive(Debug, Default)]
+pub struct Visualizer {
+    pub enabled: bool,
+    /// Where the pointer was last seen, whether or not the canvas saw it.
+    pointer: Option<Point>,
+    ripples: Vec<Ripple>,
+    keys: VecDeque<(String, Instant)>,
+}
+
+impl Visualizer {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.pointer = None;
+        self.ripples.clear();
+        self.keys.clear();
+    }
+
+    pub fn point(&mut self, position: Point) {
+        self.pointer = Some(position);
+    }
+
+    /// Shows a ripple where the pointer was last seen; clicks before the
+    /// pointer has moved are skipped.
+    pub fn click(&mut self, button: mouse::Button, now: Instant) {
+        if let Some(position) = self.pointer.filter(|_| self.enabled) {
+            self.ripples.push(Ripple {
+                position,
+                button,
+                at: now,
+            });
+        }
+    }
+
+    pub fn key(&mut self, key_code: keyboard::KeyCode, modifiers: keyboard::Modifiers, now: Instant) {
+        if !self.enabled || is_modifier(key_code) {
+            return;
+        }
+
+        self.keys.push_back((label(key_code, modifiers), now));
+
+        if self.keys.len() > MAX_KEYS {
+            self.keys.pop_front();
+        }
+    }
+
+    pub fn prune(&mut self, now: Instant) {
+        self.ripples
+            .retain(|ripple| now.duration_since(ripple.at) < RIPPLE);
+        self.keys
+            .retain(|(_, at)| now.duration_since(*at) < KEY_FADE);
+    }
+
+    pub fn is_animating(&self) -> bool {
+        !self.ripples.is_empty() || !self.keys.is_empty()
+    }
+
+    /// The ripples on screen, with how far along they are from `0.0` to `1.0`.
+    pub fn ripples(&self, now: Instant) -> impl Iterator<Item = (&Ripple, f32)> + '_ {
+        self.ripples.iter().map(move |ripple| (ripple, progress(ripple.at, RIPPLE, now)))
+    }
+
+    /// The recent key presses, oldest first, with their opacity.
+    pub fn keys(&self, now: Instant) -> impl Iterator<Item = (&str, f32)> + '_ {
+        self.keys
+            .iter()
+            .map(move |(label, at)| (label.as_str(), 1.0 - progress(*at, KEY_FADE, now)))
+    }
+}
+
+fn progress(at: Instant, duration: Duration, now: Instant) -> f32 {
+    (now.saturating_duration_since(at).as_secs_f32() / duration.as_secs_f32()).min(1.0)
+}
+
+fn is_modifier(key_code: keyboard::KeyCode) -> bool {
+    use keyboard::KeyCode;
+
+    matches!(
+        key_code,
+        KeyCode::LShift
+            | KeyCode::RShift
+            | KeyCode::LControl
+            | KeyCode::RControl
+            | KeyCode::LAlt
+            | KeyCode::RAlt
+            | KeyCode::LWin
+            | KeyCode::RWin
+    )
+}
+
+/// A label such as `Ctrl+Shift+Z` for a key press.
+pub fn label(key_code: keyboard::KeyCode, modifiers: keyboard::Modifiers) -> String {
+    let mut label = String::new();
+
+    for (pressed, name) in [
+        (modifiers.control(), "Ctrl+"),
+        (modifiers.alt(), "Alt+"),
+        (modifiers.shift(), "Shift+"),
+        (modifiers.logo(), "Super+"),
+    ] {
+        if pressed {
+            label.push_str(name);
+        }
+    }
+
+    let name = format!("{key_code:?}");
+
+    // Digits are named `Key1`, `Key2`... in `KeyCode`.
+    match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => label.push_str(digit),
+        _ => label.push_str(&name),
+    }
+
+    label
+}
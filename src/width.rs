@@ -0,0 +1,30 @@
+pub const MIN: f32 = 1.0;
+pub const MAX: f32 = 100.0;
+
+/// Pen widths stepped through with `[` and `]`.
+pub const PRESETS: [f32; 7] = [2.0, 4.0, 6.0, 10.0, 16.0, 24.0, 40.0];
+
+/// Each wheel notch scales the width by this factor, so thin and thick pens
+/// both change at a comfortable pace.
+const SCROLL_FACTOR: f32 = 1.15;
+
+pub fn scroll(width: f32, lines: f32) -> f32 {
+    (width * SCROLL_FACTOR.powf(lines)).clamp(MIN, MAX)
+}
+
+pub fn next_preset(width: f32) -> f32 {
+    PRESETS
+        .iter()
+        .copied()
+        .find(|preset| *preset > width)
+        .unwrap_or(PRESETS[PRESETS.len() - 1])
+}
+
+pub fn previous_preset(width: f32) -> f32 {
+    PRESETS
+        .iter()
+        .rev()
+        .copied()
+        .find(|preset| *preset < width)
+        .unwrap_or(PRESETS[0])
+}